@@ -0,0 +1,390 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use chrono::{Local, NaiveDate, TimeZone};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::Deserialize;
+use walkdir::WalkDir;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::i18n;
+
+/// An archive format bod can read and write. The `archive_format` config
+/// key picks the one the compress action writes.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveFormat {
+    #[default]
+    TarGz,
+    Tar,
+    Zip,
+}
+
+impl ArchiveFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarGz => "tar.gz",
+            ArchiveFormat::Tar => "tar",
+            ArchiveFormat::Zip => "zip",
+        }
+    }
+
+    /// Recognises an archive by its file name, returning the format and the
+    /// name without the extension.
+    pub fn detect(file_name: &str) -> Option<(ArchiveFormat, &str)> {
+        let lower = file_name.to_ascii_lowercase();
+        [
+            (".tar.gz", ArchiveFormat::TarGz),
+            (".tgz", ArchiveFormat::TarGz),
+            (".tar", ArchiveFormat::Tar),
+            (".zip", ArchiveFormat::Zip),
+        ]
+        .into_iter()
+        .find(|(extension, _)| lower.ends_with(extension) && lower.len() > extension.len())
+        .map(|(extension, format)| (format, &file_name[..file_name.len() - extension.len()]))
+    }
+}
+
+/// An archive being written or extracted on a background thread. `poll`
+/// picks up progress and the final result.
+pub struct ArchiveJob {
+    /// What's happening, for the status bar, e.g. `Compressing src`.
+    pub label: String,
+    pub done: usize,
+    pub total: usize,
+    pub status: JobStatus,
+    rx: Receiver<Progress>,
+}
+
+pub enum JobStatus {
+    Running,
+    /// Finished; holds the archive or directory that was written.
+    Finished(PathBuf),
+    Failed(String),
+}
+
+enum Progress {
+    Step { done: usize, total: usize },
+    Finished(io::Result<PathBuf>),
+}
+
+impl ArchiveJob {
+    /// Starts compressing `source` into an archive next to it, named after
+    /// it. An existing archive of that name is never overwritten; a number
+    /// is added instead.
+    pub fn compress(source: &Path, name: &str, format: ArchiveFormat) -> ArchiveJob {
+        let (tx, rx) = mpsc::channel();
+        let source = source.to_path_buf();
+        let name = name.to_string();
+        let label = i18n::trf("compressing", &[("name", &name)]);
+        thread::spawn(move || {
+            let result = write_archive(&source, &name, format, &tx);
+            let _ = tx.send(Progress::Finished(result));
+        });
+        ArchiveJob { label, done: 0, total: 0, status: JobStatus::Running, rx }
+    }
+
+    /// Starts extracting `archive` into a new sibling directory named after
+    /// it, adding a number if that name is taken.
+    pub fn extract(archive: &Path, name: &str, format: ArchiveFormat) -> ArchiveJob {
+        let (tx, rx) = mpsc::channel();
+        let archive = archive.to_path_buf();
+        let label = i18n::trf("extracting", &[("name", &name)]);
+        let stem = ArchiveFormat::detect(name).map_or(name, |(_, stem)| stem).to_string();
+        thread::spawn(move || {
+            let result = extract_archive(&archive, &stem, format, &tx);
+            let _ = tx.send(Progress::Finished(result));
+        });
+        ArchiveJob { label, done: 0, total: 0, status: JobStatus::Running, rx }
+    }
+
+    /// Collects progress without blocking.
+    pub fn poll(&mut self) {
+        loop {
+            match self.rx.try_recv() {
+                Ok(Progress::Step { done, total }) => {
+                    self.done = done;
+                    self.total = total;
+                },
+                Ok(Progress::Finished(Ok(path))) => self.status = JobStatus::Finished(path),
+                Ok(Progress::Finished(Err(e))) => self.status = JobStatus::Failed(e.to_string()),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if matches!(self.status, JobStatus::Running) {
+                        self.status = JobStatus::Failed("archive thread exited unexpectedly".to_string());
+                    }
+                    break;
+                },
+            }
+        }
+    }
+}
+
+/// First of `name.ext`, `name-1.ext`, `name-2.ext`... that doesn't exist
+/// in `dir`. An empty `extension` gives `name`, `name-1`...
+fn unused_path(dir: &Path, name: &str, extension: &str) -> PathBuf {
+    let with_extension = |name: String| match extension {
+        "" => name,
+        _ => format!("{}.{}", name, extension),
+    };
+    let mut path = dir.join(with_extension(name.to_string()));
+    let mut n = 1;
+    while path.exists() {
+        path = dir.join(with_extension(format!("{}-{}", name, n)));
+        n += 1;
+    }
+    path
+}
+
+fn write_archive(source: &Path, name: &str, format: ArchiveFormat, tx: &Sender<Progress>) -> io::Result<PathBuf> {
+    let dir = source.parent().unwrap_or(Path::new("."));
+    let target = unused_path(dir, name, format.extension());
+
+    // Symlinks are stored as links, never followed.
+    let entries: Vec<walkdir::DirEntry> = WalkDir::new(source)
+        .into_iter()
+        .collect::<Result<_, _>>()
+        .map_err(io::Error::from)?;
+    let total = entries.len();
+
+    let file = File::create(&target)?;
+    let result = match format {
+        ArchiveFormat::TarGz => {
+            write_tar(GzEncoder::new(file, Compression::default()), dir, &entries, tx, total)
+                .and_then(|encoder| encoder.finish().map(drop))
+        },
+        ArchiveFormat::Tar => write_tar(file, dir, &entries, tx, total).map(drop),
+        ArchiveFormat::Zip => write_zip(file, dir, &entries, tx, total),
+    };
+    if result.is_err() {
+        // Don't leave a half-written archive behind.
+        let _ = fs::remove_file(&target);
+    }
+    result.map(|()| target)
+}
+
+/// Writes a tar stream into `out`, returning it so a compressor can be
+/// finished.
+fn write_tar<W: io::Write>(out: W, base: &Path, entries: &[walkdir::DirEntry], tx: &Sender<Progress>, total: usize) -> io::Result<W> {
+    let mut builder = tar::Builder::new(out);
+    builder.follow_symlinks(false);
+    for (i, entry) in entries.iter().enumerate() {
+        let name = entry.path().strip_prefix(base).unwrap_or(entry.path());
+        builder.append_path_with_name(entry.path(), name)?;
+        let _ = tx.send(Progress::Step { done: i + 1, total });
+    }
+    builder.into_inner()
+}
+
+fn write_zip(file: File, base: &Path, entries: &[walkdir::DirEntry], tx: &Sender<Progress>, total: usize) -> io::Result<()> {
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (i, entry) in entries.iter().enumerate() {
+        let name = entry.path().strip_prefix(base).unwrap_or(entry.path()).to_string_lossy().into_owned();
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            zip.add_directory(name, options)?;
+        } else if file_type.is_symlink() {
+            let target = fs::read_link(entry.path())?;
+            zip.add_symlink(name, target.to_string_lossy(), options)?;
+        } else {
+            zip.start_file(name, options)?;
+            io::copy(&mut File::open(entry.path())?, &mut zip)?;
+        }
+        let _ = tx.send(Progress::Step { done: i + 1, total });
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+fn extract_archive(archive: &Path, stem: &str, format: ArchiveFormat, tx: &Sender<Progress>) -> io::Result<PathBuf> {
+    let dir = archive.parent().unwrap_or(Path::new("."));
+    let target = unused_path(dir, stem, "");
+    fs::create_dir(&target)?;
+    let result = match format {
+        ArchiveFormat::TarGz => extract_tar(archive, &target, tx, |file| Box::new(GzDecoder::new(file))),
+        ArchiveFormat::Tar => extract_tar(archive, &target, tx, |file| Box::new(file)),
+        ArchiveFormat::Zip => extract_zip(archive, &target, tx),
+    };
+    if result.is_err() {
+        let _ = fs::remove_dir_all(&target);
+    }
+    result.map(|()| target)
+}
+
+/// Unpacks a tar stream, reading it twice: once to count entries for the
+/// progress display, then for real. `unpack_in` refuses entries that would
+/// land outside `target`, including through `..`, absolute paths or
+/// symlinks.
+fn extract_tar(
+    archive: &Path,
+    target: &Path,
+    tx: &Sender<Progress>,
+    open: impl Fn(File) -> Box<dyn io::Read>,
+) -> io::Result<()> {
+    let total = tar::Archive::new(open(File::open(archive)?)).entries()?.count();
+    let mut tar = tar::Archive::new(open(File::open(archive)?));
+    for (i, entry) in tar.entries()?.enumerate() {
+        let mut entry = entry?;
+        if !entry.unpack_in(target)? {
+            return Err(unsafe_entry(&entry.path()?.to_string_lossy()));
+        }
+        let _ = tx.send(Progress::Step { done: i + 1, total });
+    }
+    Ok(())
+}
+
+/// Unpacks a zip. Entries whose names would escape `target` are refused.
+/// Symlink entries are skipped, since a link pointing outside `target` is
+/// another way to write there.
+fn extract_zip(archive: &Path, target: &Path, tx: &Sender<Progress>) -> io::Result<()> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    let total = zip.len();
+    for i in 0..total {
+        let mut file = zip.by_index(i)?;
+        let Some(name) = file.enclosed_name() else {
+            return Err(unsafe_entry(file.name()));
+        };
+        let path = target.join(name);
+        if file.is_dir() {
+            fs::create_dir_all(&path)?;
+        } else if !file.is_symlink() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            io::copy(&mut file, &mut File::create(&path)?)?;
+            #[cfg(unix)]
+            if let Some(mode) = file.unix_mode() {
+                use std::os::unix::fs::PermissionsExt;
+                fs::set_permissions(&path, fs::Permissions::from_mode(mode & 0o777))?;
+            }
+        }
+        let _ = tx.send(Progress::Step { done: i + 1, total });
+    }
+    Ok(())
+}
+
+fn unsafe_entry(name: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("refusing entry outside the target directory: {}", name))
+}
+
+/// An entry of an archive, as listed without unpacking anything.
+pub struct Member {
+    /// Relative to the top of the archive.
+    pub path: PathBuf,
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Everything in `archive`, read straight from it. Directories only implied
+/// by the paths of files inside them are added, so the contents can be
+/// walked like a tree. Entries whose names would point outside the archive
+/// are left out.
+pub fn list(archive: &Path, format: ArchiveFormat) -> io::Result<Vec<Member>> {
+    let mut members = match format {
+        ArchiveFormat::TarGz | ArchiveFormat::Tar => {
+            let mut tar = tar::Archive::new(open_tar(archive, format)?);
+            let mut members = Vec::new();
+            for entry in tar.entries()? {
+                let entry = entry?;
+                let Some(path) = member_path(&entry.path()?) else {
+                    continue;
+                };
+                let header = entry.header();
+                members.push(Member {
+                    path,
+                    is_dir: header.entry_type().is_dir(),
+                    size: header.size().unwrap_or(0),
+                    modified: header.mtime().ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs)),
+                });
+            }
+            members
+        },
+        ArchiveFormat::Zip => {
+            let mut zip = ZipArchive::new(File::open(archive)?)?;
+            let mut members = Vec::new();
+            for i in 0..zip.len() {
+                let file = zip.by_index_raw(i)?;
+                let Some(path) = member_path(Path::new(file.name())) else {
+                    continue;
+                };
+                // Zips store local time, without saying which zone.
+                let modified = file.last_modified().and_then(|t| {
+                    let date = NaiveDate::from_ymd_opt(t.year().into(), t.month().into(), t.day().into())?;
+                    let time = date.and_hms_opt(t.hour().into(), t.minute().into(), t.second().into())?;
+                    Local.from_local_datetime(&time).single().map(SystemTime::from)
+                });
+                members.push(Member { path, is_dir: file.is_dir(), size: file.size(), modified });
+            }
+            members
+        },
+    };
+    let mut seen: HashSet<PathBuf> = members.iter().map(|m| m.path.clone()).collect();
+    let mut implied = Vec::new();
+    for member in &members {
+        for dir in member.path.ancestors().skip(1).filter(|dir| !dir.as_os_str().is_empty()) {
+            if seen.insert(dir.to_path_buf()) {
+                implied.push(Member { path: dir.to_path_buf(), is_dir: true, size: 0, modified: None });
+            }
+        }
+    }
+    members.extend(implied);
+    Ok(members)
+}
+
+/// Up to `limit` bytes of the file `member` in `archive`, unpacked into
+/// memory.
+pub fn read_member(archive: &Path, format: ArchiveFormat, member: &Path, limit: u64) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match format {
+        ArchiveFormat::TarGz | ArchiveFormat::Tar => {
+            let mut tar = tar::Archive::new(open_tar(archive, format)?);
+            for entry in tar.entries()? {
+                let entry = entry?;
+                if member_path(&entry.path()?).as_deref() == Some(member) {
+                    entry.take(limit).read_to_end(&mut bytes)?;
+                    return Ok(bytes);
+                }
+            }
+        },
+        ArchiveFormat::Zip => {
+            let mut zip = ZipArchive::new(File::open(archive)?)?;
+            for i in 0..zip.len() {
+                if member_path(Path::new(zip.by_index_raw(i)?.name())).as_deref() == Some(member) {
+                    zip.by_index(i)?.take(limit).read_to_end(&mut bytes)?;
+                    return Ok(bytes);
+                }
+            }
+        },
+    }
+    Err(io::Error::new(io::ErrorKind::NotFound, format!("no {} in the archive", member.display())))
+}
+
+fn open_tar(archive: &Path, format: ArchiveFormat) -> io::Result<Box<dyn Read>> {
+    let file = File::open(archive)?;
+    Ok(match format {
+        ArchiveFormat::TarGz => Box::new(GzDecoder::new(file)),
+        _ => Box::new(file),
+    })
+}
+
+/// An entry's name as a plain relative path, without any `./`. `None` for
+/// the archive's top itself and for names that climb out of it.
+fn member_path(name: &Path) -> Option<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in name.components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {},
+            _ => return None,
+        }
+    }
+    (!path.as_os_str().is_empty()).then_some(path)
+}
@@ -0,0 +1,73 @@
+use std::env;
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Locale, TimeZone,
+};
+
+use crate::config::Config;
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d";
+const DEFAULT_TIME_FORMAT: &str = "%H:%M:%S";
+/// The default with `hide_seconds`.
+const MINUTES_TIME_FORMAT: &str = "%H:%M";
+
+/// Formats the date and time in the header according to the config.
+pub struct Clock {
+    locale: Locale,
+    date_format: String,
+    time_format: String,
+}
+
+impl Clock {
+    /// A format string chrono can't parse falls back to the default rather
+    /// than breaking the header.
+    pub fn new(config: &Config) -> Clock {
+        let default_time = if config.hide_seconds { MINUTES_TIME_FORMAT } else { DEFAULT_TIME_FORMAT };
+        Clock {
+            locale: detect_locale(config.locale.as_deref()),
+            date_format: valid_or(config.date_format.as_deref(), DEFAULT_DATE_FORMAT),
+            time_format: valid_or(config.time_format.as_deref(), default_time),
+        }
+    }
+
+    pub fn date<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        now.format_localized(&self.date_format, self.locale).to_string()
+    }
+
+    pub fn time<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        now.format_localized(&self.time_format, self.locale).to_string()
+    }
+}
+
+fn valid_or(format: Option<&str>, default: &str) -> String {
+    match format {
+        Some(format) if !StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) => format.to_string(),
+        _ => default.to_string(),
+    }
+}
+
+/// The configured locale, else the one the environment asks for dates in
+/// (`LC_ALL`, `LC_TIME`, then `LANG`), else POSIX. Encoding suffixes like
+/// `.UTF-8` and modifiers like `@euro` are ignored.
+fn detect_locale(configured: Option<&str>) -> Locale {
+    let from_env = || {
+        ["LC_ALL", "LC_TIME", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+    };
+    configured
+        .map(str::to_owned)
+        .or_else(from_env)
+        .and_then(|name| {
+            let name = name.split(['.', '@']).next().unwrap_or_default();
+            Locale::try_from(name).ok()
+        })
+        .unwrap_or(Locale::POSIX)
+}
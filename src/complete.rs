@@ -0,0 +1,136 @@
+use std::{env, fs, path::Path};
+
+use crate::PathExt;
+
+/// Tab-completion state for an input line with several candidates. The
+/// first Tab fills in what they have in common; each further Tab cycles the
+/// input through them.
+pub struct Completion {
+    pub candidates: Vec<String>,
+    /// The candidate currently filled in, once cycling has started.
+    pub selected: Option<usize>,
+}
+
+impl Completion {
+    /// Applies a Tab press to `text` given `candidates`. Returns the state
+    /// to keep for the next Tab, which is `None` unless the choice is
+    /// ambiguous.
+    pub fn start(text: &mut String, candidates: Vec<String>) -> Option<Completion> {
+        match candidates.len() {
+            0 => None,
+            1 => {
+                *text = candidates.into_iter().next().unwrap_or_default();
+                None
+            },
+            _ => {
+                let common = common_prefix(&candidates);
+                if common.len() > text.len() {
+                    *text = common;
+                }
+                Some(Completion { candidates, selected: None })
+            },
+        }
+    }
+
+    /// Fills `text` with the next candidate.
+    pub fn cycle(&mut self, text: &mut String) {
+        let next = self.selected.map_or(0, |i| (i + 1) % self.candidates.len());
+        self.selected = Some(next);
+        text.clone_from(&self.candidates[next]);
+    }
+}
+
+/// Completions for a shell command line: program names from `$PATH` for
+/// the first word, and paths relative to `cwd` after that.
+pub fn command_completions(text: &str, cwd: &Path) -> Vec<String> {
+    match text.rfind(char::is_whitespace) {
+        Some(i) => {
+            let (head, word) = text.split_at(i + 1);
+            path_completions(word, cwd, false)
+                .into_iter()
+                .map(|candidate| format!("{}{}", head, candidate))
+                .collect()
+        },
+        None if text.contains('/') => path_completions(text, cwd, false),
+        None => program_completions(text),
+    }
+}
+
+/// Executables on `$PATH` whose names start with `prefix`.
+fn program_completions(prefix: &str) -> Vec<String> {
+    let Some(path) = env::var_os("PATH") else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = env::split_paths(&path)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.flatten())
+        .filter(|entry| entry.metadata().is_ok_and(|m| m.is_file() && is_executable(&m)))
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| name.starts_with(prefix))
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &fs::Metadata) -> bool {
+    true
+}
+
+/// Completions for the last component of `text`, a path typed into a
+/// prompt, as full replacement texts in sorted order. Relative paths are
+/// resolved against `cwd`. Directories end in `/` so the next Tab carries
+/// on into them. Dotfiles are only offered once a `.` has been typed.
+pub fn path_completions(text: &str, cwd: &Path, dirs_only: bool) -> Vec<String> {
+    let (dir_part, prefix) = match text.rfind('/') {
+        Some(i) => text.split_at(i + 1),
+        None => ("", text),
+    };
+    let Ok(dir) = Path::new(dir_part).expand_home() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(cwd.join(dir)) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.path().is_dir();
+            if dirs_only && !is_dir {
+                return None;
+            }
+            Some(format!("{}{}{}", dir_part, name, if is_dir { "/" } else { "" }))
+        })
+        .collect();
+    candidates.sort();
+    candidates
+}
+
+/// The longest text every candidate starts with.
+pub fn common_prefix(candidates: &[String]) -> String {
+    let Some(first) = candidates.first() else {
+        return String::new();
+    };
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((i, a), b)| *i < len && a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0);
+    }
+    first[..len].to_string()
+}
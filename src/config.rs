@@ -0,0 +1,736 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use ratatui::widgets::BorderType;
+use serde::Deserialize;
+use toml_edit::{Array, DocumentMut, Item, Table};
+
+use crate::{archive::ArchiveFormat, export::ExportFormat, theme::ThemeName, PathExt};
+
+/// User-authored settings read from `~/.config/bod/config.toml`.
+///
+/// Every field has a default so a missing file, or a file that only sets a
+/// few keys, behaves exactly like the built-in setup.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    /// Directory whose subdirectories become tabs. Read once at startup;
+    /// `DEFAULT_PROJECTS_DIR` when unset.
+    pub projects_dir: Option<String>,
+    /// Command files are opened with, plus any arguments. Neovim when unset.
+    pub editor: Option<String>,
+    /// Shell command that opens a file, replacing `editor +LINE path`.
+    /// `{path}` is the file, quoted, and `{line}` the line to jump to.
+    pub open_file_cmd: Option<String>,
+    /// Shell command that opens a directory with `e`. `{path}` is the
+    /// directory, quoted. Runs `editor path` from inside it when unset.
+    pub open_dir_cmd: Option<String>,
+    /// Shell command that opens a changed file with `d` to review or merge
+    /// it. `{path}` is the file, quoted. Falls back on the editor's diff
+    /// mode, through git, when unset.
+    pub diff_cmd: Option<String>,
+    pub border_style: BorderStyle,
+    /// `false` draws no borders at all, the same as `border_style = "none"`.
+    pub borders: Option<bool>,
+    pub start_view: StartView,
+    pub start_popup: StartPopup,
+    pub popup_area: PopupArea,
+    /// Rows kept visible above and below the selection when the listing
+    /// scrolls, like vim's `scrolloff`.
+    pub scroll_margin: usize,
+    /// Scroll the selected entry's name sideways when it's too long to fit,
+    /// instead of cutting it short.
+    pub marquee_selected: bool,
+    /// List a `..` row at the top for going up a level. It stays first
+    /// whatever the sort and is never filtered out.
+    pub parent_entry: bool,
+    pub enter_without_selection: EnterWithoutSelection,
+    /// Re-read the current directory every this many seconds. Off when unset.
+    pub auto_refresh_secs: Option<u64>,
+    /// Re-read the listing whenever the terminal regains focus. Needs a
+    /// terminal that reports focus changes.
+    pub refresh_on_focus: bool,
+    /// Start the `/` filter matching case exactly. Tab flips it while
+    /// filtering.
+    pub search_case_sensitive: bool,
+    /// Names, or extensions starting with a dot, listed ahead of the rest
+    /// of their directories or files, in this order.
+    pub sort_first: Vec<String>,
+    pub tab_order: TabOrder,
+    /// Keys that jump straight to a tab, in tab order. Defaults to
+    /// `DEFAULT_TAB_KEYS`.
+    pub tab_keys: Option<String>,
+    pub tab_key_past_end: TabKeyPastEnd,
+    /// Project directories that never get a tab.
+    pub hidden_tabs: Vec<String>,
+    pub launch_mode: LaunchMode,
+    pub launch_focus: LaunchFocus,
+    /// Ask before opening a file again within this many seconds of opening
+    /// it. Off when unset.
+    pub reopen_confirm_secs: Option<u64>,
+    /// Exit once a file or directory has been opened, for using bod only
+    /// to pick what to open.
+    pub quit_after_open: bool,
+    /// Cancel a y/n popup left unanswered this many seconds. Off when unset.
+    pub confirm_timeout_secs: Option<u64>,
+    pub on_conflict: OnConflict,
+    pub theme: ThemeName,
+    /// Draw image files in the preview on terminals with kitty or iTerm
+    /// graphics. Elsewhere, or when off, only their size is shown.
+    pub image_previews: bool,
+    /// Where the tabs start out; b flips between the two while running.
+    pub tab_position: TabPosition,
+    pub tab_highlight: TabHighlight,
+    /// Drawn between tab titles. ratatui's vertical bar when unset.
+    pub tab_divider: Option<String>,
+    /// Longest a tab's name may be in the tab bar, in columns, before it's
+    /// cut short with `…`. Not counting the key in front. No limit when unset.
+    pub tab_title_max: Option<usize>,
+    /// Template for the listing's title. The translation of "Contents of
+    /// {path}" when unset.
+    pub contents_title: Option<String>,
+    pub archive_format: ArchiveFormat,
+    pub export_format: ExportFormat,
+    /// strftime-style formats for the header clock.
+    pub date_format: Option<String>,
+    pub time_format: Option<String>,
+    /// Leave the seconds out of the default `time_format`, so the header
+    /// only changes once a minute.
+    pub hide_seconds: bool,
+    /// Relative widths of the header's date, name and time cells. Defaults
+    /// to `DEFAULT_HEADER_SPLIT`.
+    pub header_split: Option<[u32; 3]>,
+    /// Locale for month and day names, AM/PM and UI text, e.g. `de_DE`. Taken from
+    /// the environment when unset.
+    pub locale: Option<String>,
+    /// Dim the whole UI after this many seconds without input. Off when
+    /// unset.
+    pub idle_dim_secs: Option<u64>,
+    /// Show the free space on the projects directory's filesystem in the
+    /// status bar.
+    pub show_free_space: bool,
+    /// Show the projects overview (also reachable with `0`) as the first tab.
+    pub projects_tab: bool,
+    /// Reopen each tab in the subdirectory it was left in rather than at
+    /// its root.
+    pub remember_tab_paths: bool,
+    /// Tab `H` goes to the root of. The first tab when unset or when there's
+    /// no tab by that name.
+    pub default_project: Option<String>,
+    /// How many directories deep `r` looks for recently modified files.
+    /// Defaults to `DEFAULT_RECENT_DEPTH`.
+    pub recent_depth: Option<usize>,
+    /// Files listed before everything else, keyed by project name. Paths are
+    /// relative to the project root.
+    pub pinned: HashMap<String, Vec<String>>,
+    /// Command `R` runs for each project, by project name. Projects not
+    /// listed get one guessed from their build files.
+    pub tasks: HashMap<String, String>,
+    /// Extra tabs for directories outside the projects directory, label to
+    /// path. `A` in the app adds one.
+    pub custom_tabs: HashMap<String, String>,
+    /// Emoji or short labels shown before tab names, by tab name.
+    pub tab_icons: HashMap<String, String>,
+    /// Wording of the y/n popups, by kind: `open`, `reopen`, `extract` and
+    /// `overwrite`.
+    /// `{name}` and `{editor}` are filled in.
+    pub confirmations: HashMap<String, String>,
+    pub on_startup: OnStartup,
+    /// Styles for entries whose names match a pattern; the first match wins.
+    pub color_rules: Vec<ColorRule>,
+    /// Shell commands bound to keys no built-in action uses.
+    pub actions: Vec<Action>,
+    /// Extra editor arguments for files and directories in a project, by
+    /// project name. Added after `editor`, or in place of `{args}` in
+    /// `open_file_cmd` and `open_dir_cmd`.
+    pub editor_args: HashMap<String, String>,
+    /// Programs to open files with by extension (lowercase, no dot), tried
+    /// before the editor. The path is added as the last argument.
+    pub openers: HashMap<String, String>,
+    /// Commands `f` tidies a file with, by extension (lowercase, no dot).
+    /// The path is added as the last argument.
+    pub formatters: HashMap<String, String>,
+}
+
+/// One `[[actions]]` entry. `{path}` in the command becomes the selected
+/// entry (the current directory when nothing is selected) and `{dir}` the
+/// current directory, both quoted for the shell.
+#[derive(Deserialize)]
+pub struct Action {
+    pub key: String,
+    pub command: String,
+    /// Show the output in a popup, as `:` does, rather than just the
+    /// outcome in the status bar.
+    #[serde(default)]
+    pub show_output: bool,
+}
+
+/// One `[[color_rules]]` entry: a glob matched against entry names, and
+/// the colour and modifier matching entries are drawn with.
+#[derive(Deserialize)]
+pub struct ColorRule {
+    pub pattern: String,
+    pub color: Option<String>,
+    pub modifier: Option<String>,
+}
+
+/// What to do once the UI is up, in order: switch to `open_tab`, then run
+/// `command` in the background from the directory that leaves showing.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct OnStartup {
+    pub open_tab: Option<String>,
+    pub command: Option<String>,
+}
+
+pub const DEFAULT_TAB_KEYS: &str = "123456789";
+
+pub const DEFAULT_RECENT_DEPTH: usize = 4;
+
+pub const DEFAULT_HEADER_SPLIT: [u32; 3] = [33, 34, 33];
+pub const DEFAULT_PROJECTS_DIR: &str = "~/Documents/rakesh/projects";
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderStyle {
+    #[default]
+    Plain,
+    Rounded,
+    Double,
+    Thick,
+    /// No borders at all. Bars shrink to a single row and panels are set
+    /// apart by their titles and a column of padding instead.
+    None,
+}
+
+impl BorderStyle {
+    pub fn is_none(self) -> bool {
+        matches!(self, BorderStyle::None)
+    }
+}
+
+/// Whether the directory listing is visible at startup; `c` toggles it.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StartView {
+    #[default]
+    Content,
+    Hidden,
+}
+
+/// A popup to show over the first screen.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StartPopup {
+    #[default]
+    None,
+    EditorSelection,
+    Welcome,
+}
+
+/// How tabs are ordered.
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum TabOrder {
+    /// By project name. Projects not listed keep their discovery order
+    /// after the listed ones.
+    Names(Vec<String>),
+    /// Most visited lately first, as of startup; see `Visits`.
+    Frecency(Frecency),
+}
+
+impl Default for TabOrder {
+    fn default() -> TabOrder {
+        TabOrder::Names(Vec::new())
+    }
+}
+
+/// The `"frecency"` in `tab_order = "frecency"`.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frecency {
+    Frecency,
+}
+
+/// Where popups are centred.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PopupArea {
+    /// Below the header, tab bar and shortcuts, so they stay visible.
+    #[default]
+    Content,
+    Screen,
+}
+
+/// What a tab key does when there are fewer tabs than keys.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TabKeyPastEnd {
+    /// Stay put and say there's no such tab.
+    #[default]
+    Ignore,
+    /// Go to the last tab.
+    Last,
+}
+
+/// How the editor is started when a file is opened.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchMode {
+    /// Suspend the TUI and run the editor in this terminal until it exits.
+    Foreground,
+    /// Spawn the editor in a separate terminal window.
+    #[default]
+    NewWindow,
+}
+
+/// Which window ends up focused after a `new_window` launch.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchFocus {
+    /// Leave it to the window manager.
+    #[default]
+    Default,
+    /// Take focus back to the terminal running bod.
+    Tui,
+    /// Make sure the new editor window is raised.
+    Editor,
+}
+
+/// What Enter does in a listing with nothing selected.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum EnterWithoutSelection {
+    /// Select the first entry, as the arrow keys would.
+    #[default]
+    SelectFirst,
+    /// Open the directory being listed in the editor, like `e`.
+    OpenDir,
+    Nothing,
+}
+
+/// What copying or moving does about entries already at the destination.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    /// Ask, offering the other three.
+    #[default]
+    Ask,
+    Skip,
+    Overwrite,
+    /// Give the new copy a free name like `notes (2).txt`.
+    Rename,
+}
+
+/// Where the tabs are listed.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TabPosition {
+    /// A bar across the top.
+    #[default]
+    Top,
+    /// A list down the left, which scrolls and suits many projects.
+    Side,
+}
+
+/// How the tab bar marks the current tab.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TabHighlight {
+    /// The theme's accent colour on the title.
+    #[default]
+    Text,
+    /// The title drawn on a filled accent background.
+    Background,
+}
+
+impl From<BorderStyle> for BorderType {
+    fn from(style: BorderStyle) -> BorderType {
+        match style {
+            BorderStyle::Plain | BorderStyle::None => BorderType::Plain,
+            BorderStyle::Rounded => BorderType::Rounded,
+            BorderStyle::Double => BorderType::Double,
+            BorderStyle::Thick => BorderType::Thick,
+        }
+    }
+}
+
+/// Written to a fresh config file so every option is discoverable. All of
+/// it is commented out, so it behaves exactly like having no file.
+const DEFAULT_CONFIG: &str = r##"# bod configuration. Every key is optional; uncomment to change.
+
+# Directory whose subdirectories become tabs. Only read at startup
+# projects_dir = "~/Documents/rakesh/projects"
+
+# Command files are opened with; +LINE is added when jumping to a line
+# editor = "nvim"
+
+# Shell commands to open a file, or a directory with e, instead of the
+# editor. {path} is quoted for you, {line} is the line to jump to and {args}
+# the project's [editor_args]. Directories otherwise open with the editor
+# started on them from inside them
+# open_file_cmd = "code --goto {path}:{line}"
+# open_dir_cmd = "code {path}"
+
+# Shell command d opens a file git reports as changed with, to review or
+# merge it. {path} is quoted for you. Unset, a vim or nvim editor is started
+# through git difftool, or git mergetool for a file with conflicts
+# diff_cmd = "meld {path}"
+
+# Border style for every panel: "plain", "rounded", "double", "thick", or
+# "none" for a minimal look with the room borders took given to content
+# border_style = "plain"
+
+# Set to false for that minimal look whatever border_style says
+# borders = false
+
+# Start with the listing "content" or "hidden"; c toggles it in the app
+# start_view = "content"
+
+# Popup over the first screen: "none", "editor_selection" or "welcome"
+# start_popup = "none"
+
+# Centre popups in the "content" area, leaving the header, tabs and
+# shortcuts visible, or on the whole "screen"
+# popup_area = "content"
+
+# Rows kept visible above and below the selection while scrolling
+# scroll_margin = 0
+
+# Scroll a selected name that's too long to fit back and forth so all of it
+# can be read; other names are still cut short
+# marquee_selected = false
+
+# A .. row at the top of the listing that goes up a level, or out of an
+# archive. It stays first however the listing is sorted or filtered
+# parent_entry = false
+
+# What Enter does when nothing is selected: "select_first" entry, "open_dir"
+# to open the directory being listed in the editor like e, or "nothing"
+# enter_without_selection = "select_first"
+
+# Re-read the current directory every N seconds (off when unset)
+# auto_refresh_secs = 5
+
+# Re-read the listing when the terminal window regains focus, e.g. after
+# editing files in another window. Needs a terminal that reports focus
+# refresh_on_focus = false
+
+# Make the / filter tell upper and lower case apart; Tab flips this while
+# typing a filter
+# search_case_sensitive = false
+
+# Entries listed first among the directories or among the files, in this
+# order, whatever the sort. Either whole names or extensions starting with .
+# sort_first = ["Cargo.toml", "README.md", ".md"]
+
+# Tab order by project name; Ctrl+Left/Right in the app rewrites this.
+# "frecency" puts the tabs switched to most often and most lately first,
+# sorted once at startup
+# tab_order = ["bod", "website"]
+
+# Keys that jump straight to tabs, first key to first tab. Keys other
+# actions already use are ignored. ' followed by a number reaches any tab
+# tab_keys = "123456789"
+
+# A tab key past the last tab can "ignore" the key, saying there's no such
+# tab, or go to the "last" tab instead
+# tab_key_past_end = "ignore"
+
+# Project directories to leave out of the tab bar; h hides and U restores
+# hidden_tabs = ["archive"]
+
+# "new_window" spawns the editor in a new alacritty window; "foreground"
+# suspends the TUI and runs it in this terminal until it exits
+# launch_mode = "new_window"
+
+# Which window gets focus after a new_window launch: "default" leaves it to
+# the window manager, "tui" takes it back, "editor" raises the new window.
+# Needs wmctrl/xdotool on X11 or osascript on macOS; ignored without them
+# launch_focus = "default"
+
+# Ask before opening the same file again within N seconds (off when unset)
+# reopen_confirm_secs = 10
+
+# Exit as soon as something has been opened. With launch_mode = "foreground"
+# that's when the editor closes
+# quit_after_open = false
+
+# Cancel a y/n popup nobody answers within N seconds (off when unset)
+# confirm_timeout_secs = 30
+
+# When copying or moving meets an entry of the same name: "ask", "skip" it,
+# "overwrite" it (undo can't bring it back) or "rename" the new one
+# on_conflict = "ask"
+
+# Colour theme: "default", "gruvbox", "nord", "terminal" to inherit the
+# terminal's own colours, or "monochrome". Monochrome is also used whenever
+# NO_COLOR is set or the terminal has no colour support
+# theme = "default"
+
+# Draw images in the preview on terminals with kitty or iTerm graphics
+# (kitty, WezTerm, iTerm2); elsewhere only their size is shown
+# image_previews = false
+
+# Tabs across the "top", or listed down the "side" (b switches while running)
+# tab_position = "top"
+
+# Current tab in the tab bar: "text" colours the title, "background" fills
+# behind it. tab_divider goes between tab titles, and tab_title_max cuts
+# long names short with … (the key in front is kept)
+# tab_highlight = "text"
+# tab_divider = "│"
+# tab_title_max = 20
+
+# Title of the listing. {path} is the current directory, {tab} the tab
+# name, {count} the number of entries and {selected} the selected one
+# contents_title = "Contents of {path}"
+
+# Format z compresses into: "tar_gz", "tar" or "zip"
+# archive_format = "tar_gz"
+
+# Format X exports the listing in: "text", "markdown" or "json"
+# export_format = "text"
+
+# Header date and time, as strftime formats. %B, %A and %p follow the
+# locale, which defaults to LC_ALL/LC_TIME/LANG. For a 12-hour clock use
+# time_format = "%I:%M:%S %p". The locale also picks the UI language
+# (see Translations in the README)
+# date_format = "%Y-%m-%d"
+# time_format = "%H:%M:%S"
+# locale = "en_US"
+
+# Show the time as %H:%M, so the screen is left alone for a minute at a time
+# rather than redrawn every second. Only changes the default time_format
+# hide_seconds = false
+
+# Relative widths of the header's date, name and time. On a window too
+# narrow for all three the name is left out
+# header_split = [33, 34, 33]
+
+# Dim the UI after N seconds without input; any key wakes it (off when unset)
+# idle_dim_secs = 300
+
+# Show how much space is free where the projects directory lives, checked
+# every half minute
+# show_free_space = false
+
+# Show an "All Projects" tab listing the projects directory itself; 0 jumps
+# there either way
+# projects_tab = false
+
+# Switching to a tab returns to the directory it was left in instead of its
+# root; pressing the key of the tab already shown still goes to the root
+# remember_tab_paths = false
+
+# Project H jumps to the root of from anywhere (the first tab when unset)
+# default_project = "bod"
+
+# r lists the newest files across every tab, looking this many directories
+# down each one (dot directories are skipped)
+# recent_depth = 4
+
+# Files to keep at the top of a project's listing, relative to the project
+# root; p in the app toggles the selected entry
+# [pinned]
+# bod = ["Cargo.toml", "src/main.rs"]
+
+# Command R runs in each project's root, by project name. Without one,
+# cargo run, npm start, go run . or make is picked from the build files
+# [tasks]
+# bod = "cargo test"
+
+# Run on launch: open_tab switches to a project, command runs in the
+# background and only reports back if it fails
+# [on_startup]
+# open_tab = "bod"
+# command = "git fetch --quiet"
+
+# Tabs for directories anywhere, label = path; A in the app adds the
+# selected directory as one
+# [custom_tabs]
+# dotfiles = "~/.config"
+
+# Emoji or a short label in front of a tab's name, by tab name
+# [tab_icons]
+# bod = "🦀"
+# website = "web"
+
+# Extra editor arguments per project, by project name. They go after the
+# editor command, or wherever {args} is in open_file_cmd and open_dir_cmd
+# [editor_args]
+# website = "-u ~/.config/nvim/web.lua"
+
+# Programs to open files with by extension, run in the background with the
+# path added at the end. Opening a file tries these first, then the editor,
+# then the OS default app, skipping any that isn't installed
+# [openers]
+# pdf = "zathura"
+# png = "feh --scale-down"
+
+# Formatters f runs on the selected file, by extension, with the path added
+# at the end. How it went shows in the status bar
+# [formatters]
+# rs = "rustfmt"
+# js = "prettier --write"
+
+# Wording of the y/n popups. {name} is the selected entry and {editor} the
+# editor; the keys to answer with are added after
+# [confirmations]
+# open = "Open {name} in {editor}?"
+# reopen = "Already opened {name}, open again?"
+# extract = "Extract {name} here?"
+# overwrite = "Overwrite {name}?"
+
+# Styles for entries by name, checked in order; the first matching pattern
+# wins. color is a name ("blue", "light_red") or "#rrggbb"; modifier is one
+# of bold, dim, italic, underlined, reversed or crossed_out
+# [[color_rules]]
+# pattern = "*.test.*"
+# modifier = "dim"
+#
+# [[color_rules]]
+# pattern = "Dockerfile"
+# color = "blue"
+
+# Commands bound to keys bod doesn't use itself, run from the current
+# directory. {path} is the selected entry and {dir} the directory.
+# show_output opens the output in a popup; otherwise only failures are
+# reported. The listing is re-read once the command finishes. An action
+# on a key bod already uses is reported at startup and never runs.
+# [[actions]]
+# key = "B"
+# command = "prettier --write {path}"
+#
+# [[actions]]
+# key = "L"
+# command = "cargo clippy"
+# show_output = true
+"##;
+
+impl Config {
+    pub fn path() -> io::Result<PathBuf> {
+        Path::new("~/.config/bod/config.toml").expand_home()
+    }
+
+    /// Returns the config file path, first writing the commented defaults
+    /// there if no file exists yet.
+    pub fn ensure_exists() -> io::Result<PathBuf> {
+        let path = Config::path()?;
+        if !path.exists() {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, DEFAULT_CONFIG)?;
+        }
+        Ok(path)
+    }
+
+    /// Loads the config file, falling back to defaults when it doesn't exist.
+    pub fn load() -> io::Result<Config> {
+        let path = Config::path()?;
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e),
+        };
+        let mut config: Config = toml::from_str(&text).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+        })?;
+        if config.borders == Some(false) {
+            config.border_style = BorderStyle::None;
+        }
+        Ok(config)
+    }
+}
+
+/// Sets a single top-level key in the config file, creating the file if
+/// needed. Edits the document in place so comments and formatting the user
+/// wrote are preserved.
+pub fn set_key(key: &str, value: Item) -> io::Result<()> {
+    let path = Config::path()?;
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+    let mut doc: DocumentMut = text.parse().map_err(|e| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+    })?;
+    doc[key] = value;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, doc.to_string())
+}
+
+/// The config representation of a list of strings.
+pub fn string_list(items: &[String]) -> Item {
+    toml_edit::value(items.iter().map(String::as_str).collect::<Array>())
+}
+
+/// The config representation of a map from names to lists of strings.
+pub fn string_list_table(map: &HashMap<String, Vec<String>>) -> Item {
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+    let mut table = Table::new();
+    for key in keys {
+        table.insert(key, string_list(&map[key]));
+    }
+    Item::Table(table)
+}
+
+/// The config representation of a string-to-string map, as a table with
+/// sorted keys.
+pub fn string_table(map: &HashMap<String, String>) -> Item {
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+    let mut table = Table::new();
+    for key in keys {
+        table.insert(key, toml_edit::value(map[key].as_str()));
+    }
+    Item::Table(table)
+}
+
+/// Watches the config file so edits made anywhere take effect live.
+///
+/// The parent directory is watched rather than the file itself because most
+/// editors save by writing a new file and renaming it over the old one.
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+    path: PathBuf,
+}
+
+impl ConfigWatcher {
+    /// Starts watching, or returns `None` if the config directory doesn't
+    /// exist yet or the platform watcher can't be created.
+    pub fn start() -> Option<ConfigWatcher> {
+        let path = Config::path().ok()?;
+        let dir = path.parent()?;
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).ok()?;
+        watcher.watch(dir, RecursiveMode::NonRecursive).ok()?;
+        Some(ConfigWatcher { _watcher: watcher, rx, path })
+    }
+
+    /// True if the config file changed since the last call. Drains every
+    /// pending event so a burst of writes only triggers one reload.
+    pub fn changed(&self) -> bool {
+        let mut changed = false;
+        while let Ok(event) = self.rx.try_recv() {
+            if let Ok(event) = event {
+                let relevant = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+                if relevant && event.paths.contains(&self.path) {
+                    changed = true;
+                }
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,104 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    process::{Command, Stdio},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{launch, usage::human_size};
+
+/// How `X` writes out the listing. The `export_format` config key picks
+/// one.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    /// One entry per line, directories ending in `/`.
+    #[default]
+    Text,
+    Markdown,
+    Json,
+}
+
+/// An entry of the listing being exported.
+pub struct Row<'a> {
+    pub name: &'a str,
+    pub is_dir: bool,
+    /// In bytes. Left out for directories.
+    pub size: u64,
+}
+
+/// Clipboard tools tried in order, with the arguments that make them read
+/// stdin into the clipboard.
+const CLIPBOARD_TOOLS: &[(&str, &[&str])] = &[
+    ("pbcopy", &[]),
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+];
+
+/// `rows` laid out as `format` says, ending in a newline.
+pub fn render(rows: &[Row], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Text => rows
+            .iter()
+            .map(|row| if row.is_dir {
+                format!("{}/\n", row.name)
+            } else {
+                format!("{}  {}\n", row.name, human_size(row.size))
+            })
+            .collect(),
+        ExportFormat::Markdown => {
+            let mut table = String::from("| Name | Type | Size |\n| --- | --- | ---: |\n");
+            for row in rows {
+                // Pipes would end the cell early.
+                let name = row.name.replace('|', "\\|");
+                let (kind, size) = if row.is_dir { ("directory", String::new()) } else { ("file", human_size(row.size)) };
+                table.push_str(&format!("| {} | {} | {} |\n", name, kind, size));
+            }
+            table
+        },
+        ExportFormat::Json => {
+            let entries: Vec<_> = rows
+                .iter()
+                .map(|row| if row.is_dir {
+                    json!({ "name": row.name, "type": "directory" })
+                } else {
+                    json!({ "name": row.name, "type": "file", "size": row.size })
+                })
+                .collect();
+            let mut text = serde_json::to_string_pretty(&entries).unwrap_or_default();
+            text.push('\n');
+            text
+        },
+    }
+}
+
+/// Writes `text` to a new file at `path`. Refuses to replace one that's
+/// already there.
+pub fn to_file(text: &str, path: &Path) -> io::Result<()> {
+    File::create_new(path)?.write_all(text.as_bytes())
+}
+
+/// Hands `text` to the first clipboard tool found on the `$PATH`.
+pub fn to_clipboard(text: &str) -> io::Result<()> {
+    let (program, args) = CLIPBOARD_TOOLS
+        .iter()
+        .find(|(program, _)| launch::program_exists(program))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no clipboard tool found"))?;
+    let mut child = Command::new(program)
+        .args(*args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{} failed", program)));
+    }
+    Ok(())
+}
@@ -0,0 +1,326 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+use walkdir::WalkDir;
+
+/// What to do when `dest_dir` already has an entry by the source's name.
+#[derive(Clone, Copy)]
+pub enum Conflict {
+    /// Fail with `AlreadyExists`.
+    Refuse,
+    /// Replace what's there. It's kept aside under a hidden name until the
+    /// operation drops off the undo stack, so undo can put it back.
+    Overwrite,
+    /// Use the first free name of the form `notes (2).txt`.
+    Rename,
+}
+
+/// True if `dest_dir` already has an entry named like `source`.
+pub fn collides(source: &Path, dest_dir: &Path) -> bool {
+    source.file_name().is_some_and(|name| dest_dir.join(name).symlink_metadata().is_ok())
+}
+
+/// Where `source` ends up when copied or moved into `dest_dir`, and
+/// whether something there has to be replaced, as `conflict` says.
+/// Refuses to put a directory inside itself or to replace `source` with
+/// itself.
+fn target(source: &Path, dest_dir: &Path, conflict: Conflict) -> io::Result<(PathBuf, bool)> {
+    let name = source.file_name().ok_or_else(|| io::Error::other("nothing to copy"))?;
+    if dest_dir.starts_with(source) {
+        return Err(io::Error::other("can't copy a directory into itself"));
+    }
+    let target = dest_dir.join(name);
+    if target.symlink_metadata().is_err() {
+        return Ok((target, false));
+    }
+    match conflict {
+        Conflict::Refuse => Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", target.display()))),
+        Conflict::Overwrite if source.starts_with(&target) => Err(io::Error::other("can't replace an entry with itself")),
+        Conflict::Overwrite => Ok((target, true)),
+        Conflict::Rename => Ok((free_name(dest_dir, Path::new(name)), false)),
+    }
+}
+
+/// A hidden, unused name next to `target` for keeping something aside,
+/// like `.notes.txt.bod-replaced`.
+fn aside(target: &Path, label: &str) -> PathBuf {
+    let dir = target.parent().unwrap_or(Path::new("."));
+    let name = format!(".{}.bod-{}", target.file_name().unwrap_or_default().to_string_lossy(), label);
+    let path = dir.join(&name);
+    if path.symlink_metadata().is_err() {
+        path
+    } else {
+        free_name(dir, Path::new(&name))
+    }
+}
+
+/// The first of `name (2)`, `name (3)` and so on not taken in `dir`,
+/// keeping the extension at the end.
+fn free_name(dir: &Path, name: &Path) -> PathBuf {
+    let stem = name.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = name.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (2..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, extension)))
+        .find(|path| path.symlink_metadata().is_err())
+        .unwrap_or_default()
+}
+
+/// Copies `source`, recursively if it's a directory, into `dest_dir`.
+pub fn copy(source: &Path, dest_dir: &Path, conflict: Conflict) -> io::Result<Operation> {
+    let (target, replaces) = target(source, dest_dir, conflict)?;
+    if !replaces {
+        copy_tree(source, &target)?;
+        return Ok(Operation::Copied { copy: target, replaced: None });
+    }
+    // Built beside the target and only swapped in once it's complete, so a
+    // copy that fails partway leaves what was there alone.
+    let temp = aside(&target, "copying");
+    if let Err(e) = copy_tree(source, &temp) {
+        let _ = remove(&temp);
+        return Err(e);
+    }
+    let replaced = aside(&target, "replaced");
+    if let Err(e) = fs::rename(&target, &replaced) {
+        let _ = remove(&temp);
+        return Err(e);
+    }
+    if let Err(e) = fs::rename(&temp, &target) {
+        let _ = fs::rename(&replaced, &target);
+        let _ = remove(&temp);
+        return Err(e);
+    }
+    Ok(Operation::Copied { copy: target, replaced: Some(replaced) })
+}
+
+/// Moves `source` into `dest_dir`.
+pub fn move_to(source: &Path, dest_dir: &Path, conflict: Conflict) -> io::Result<Operation> {
+    let (target, replaces) = target(source, dest_dir, conflict)?;
+    let mut replaced = None;
+    if replaces {
+        let aside = aside(&target, "replaced");
+        fs::rename(&target, &aside)?;
+        replaced = Some(aside);
+    }
+    if let Err(e) = relocate(source, &target) {
+        // Put back what was there, unless the move got far enough that
+        // the target is all that's left of the source.
+        if let Some(aside) = &replaced {
+            if target.symlink_metadata().is_err() {
+                let _ = fs::rename(aside, &target);
+            }
+        }
+        return Err(e);
+    }
+    Ok(Operation::Moved { from: source.to_path_buf(), to: target, replaced })
+}
+
+/// Moves `source` to `target`, copying and removing the original when a
+/// rename can't cross filesystems. A copy that fails partway is cleaned
+/// up again.
+fn relocate(source: &Path, target: &Path) -> io::Result<()> {
+    if fs::rename(source, target).is_err() {
+        if let Err(e) = copy_tree(source, target) {
+            let _ = remove(target);
+            return Err(e);
+        }
+        remove(source)?;
+    }
+    Ok(())
+}
+
+/// Deletes `path`, recursively if it's a directory. Links aren't followed.
+fn remove(path: &Path) -> io::Result<()> {
+    if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// How many operations back Ctrl+Z can go.
+pub const UNDO_DEPTH: usize = 10;
+
+/// A file operation done from bod, with what's needed to reverse it.
+/// `replaced` is where an overwritten entry was put aside.
+pub enum Operation {
+    Copied { copy: PathBuf, replaced: Option<PathBuf> },
+    Moved { from: PathBuf, to: PathBuf, replaced: Option<PathBuf> },
+}
+
+impl Operation {
+    /// Reverses the operation: deletes the copy, or moves the file back to
+    /// where it was, then puts back anything it replaced. Returns the path
+    /// that now holds the result.
+    pub fn undo(&self) -> io::Result<PathBuf> {
+        match self {
+            Operation::Copied { copy, replaced } => {
+                remove(copy)?;
+                if let Some(replaced) = replaced {
+                    fs::rename(replaced, copy)?;
+                }
+                Ok(copy.clone())
+            },
+            // Back under its old name, which a rename on the way may have
+            // changed.
+            Operation::Moved { from, to, replaced } => {
+                if from.symlink_metadata().is_ok() {
+                    return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("{} already exists", from.display())));
+                }
+                relocate(to, from)?;
+                if let Some(replaced) = replaced {
+                    fs::rename(replaced, to)?;
+                }
+                Ok(from.clone())
+            },
+        }
+    }
+
+    /// Deletes what the operation replaced, once it can't be undone any
+    /// more.
+    pub fn forget(&self) {
+        let (Operation::Copied { replaced: Some(replaced), .. } | Operation::Moved { replaced: Some(replaced), .. }) = self else {
+            return;
+        };
+        if let Err(e) = remove(replaced) {
+            log::warn!("couldn't delete {}: {}", replaced.display(), e);
+        }
+    }
+}
+
+fn copy_tree(source: &Path, target: &Path) -> io::Result<()> {
+    for entry in WalkDir::new(source) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(source).map_err(io::Error::other)?;
+        // `join("")` would add a trailing slash, which a plain file can't have.
+        let dest = if relative.as_os_str().is_empty() { target.to_path_buf() } else { target.join(relative) };
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            fs::create_dir_all(&dest)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(entry.path(), &dest)?;
+        } else {
+            fs::copy(entry.path(), &dest)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(source: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(source)?, dest)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(source: &Path, dest: &Path) -> io::Result<()> {
+    fs::copy(source, dest).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, text: &str) {
+        fs::write(path, text).unwrap();
+    }
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap()
+    }
+
+    /// Names in `dir`, sorted, to check nothing was left behind.
+    fn names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn overwriting_copy_is_undone() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write(&src.path().join("notes.txt"), "new");
+        write(&dest.path().join("notes.txt"), "old");
+
+        let operation = copy(&src.path().join("notes.txt"), dest.path(), Conflict::Overwrite).unwrap();
+        assert_eq!(read(&dest.path().join("notes.txt")), "new");
+
+        operation.undo().unwrap();
+        assert_eq!(read(&dest.path().join("notes.txt")), "old");
+        assert_eq!(names(dest.path()), ["notes.txt"]);
+    }
+
+    #[test]
+    fn overwriting_move_is_undone() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write(&src.path().join("notes.txt"), "new");
+        write(&dest.path().join("notes.txt"), "old");
+
+        let operation = move_to(&src.path().join("notes.txt"), dest.path(), Conflict::Overwrite).unwrap();
+        assert_eq!(read(&dest.path().join("notes.txt")), "new");
+        assert!(names(src.path()).is_empty());
+
+        operation.undo().unwrap();
+        assert_eq!(read(&src.path().join("notes.txt")), "new");
+        assert_eq!(read(&dest.path().join("notes.txt")), "old");
+        assert_eq!(names(dest.path()), ["notes.txt"]);
+    }
+
+    #[test]
+    fn forget_deletes_the_replaced_entry() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write(&src.path().join("notes.txt"), "new");
+        write(&dest.path().join("notes.txt"), "old");
+
+        let operation = copy(&src.path().join("notes.txt"), dest.path(), Conflict::Overwrite).unwrap();
+        operation.forget();
+        assert_eq!(names(dest.path()), ["notes.txt"]);
+        assert_eq!(read(&dest.path().join("notes.txt")), "new");
+    }
+
+    #[test]
+    fn rename_picks_the_next_free_name() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write(&src.path().join("notes.txt"), "new");
+        write(&dest.path().join("notes.txt"), "old");
+        write(&dest.path().join("notes (2).txt"), "older");
+
+        let operation = copy(&src.path().join("notes.txt"), dest.path(), Conflict::Rename).unwrap();
+        assert_eq!(read(&dest.path().join("notes (3).txt")), "new");
+        assert_eq!(read(&dest.path().join("notes.txt")), "old");
+
+        operation.undo().unwrap();
+        assert_eq!(names(dest.path()), ["notes (2).txt", "notes.txt"]);
+    }
+
+    #[test]
+    fn free_name_keeps_the_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(free_name(dir.path(), Path::new("notes.txt")), dir.path().join("notes (2).txt"));
+        assert_eq!(free_name(dir.path(), Path::new("build")), dir.path().join("build (2)"));
+    }
+
+    #[test]
+    fn refuses_to_copy_a_directory_into_itself() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("project");
+        fs::create_dir_all(source.join("src")).unwrap();
+
+        assert!(copy(&source, &source.join("src"), Conflict::Rename).is_err());
+        assert!(move_to(&source, &source, Conflict::Rename).is_err());
+        assert_eq!(names(&source), ["src"]);
+    }
+
+    #[test]
+    fn refuses_to_overwrite_without_being_told() {
+        let (src, dest) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        write(&src.path().join("notes.txt"), "new");
+        write(&dest.path().join("notes.txt"), "old");
+
+        let error = copy(&src.path().join("notes.txt"), dest.path(), Conflict::Refuse).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(read(&dest.path().join("notes.txt")), "old");
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use walkdir::WalkDir;
+
+use crate::fuzzy;
+
+/// Most files the finder collects from one tab, so a huge tree can't
+/// stall the UI for long.
+const LIMIT: usize = 20_000;
+
+/// Every file under `root`, relative to it and in walk order. Dot
+/// directories are skipped, as is anything the root's `.gitignore` names.
+pub fn scan(root: &Path) -> Vec<PathBuf> {
+    let ignored = Ignored::read(root);
+    WalkDir::new(root)
+        .min_depth(1)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            let Ok(relative) = e.path().strip_prefix(root) else {
+                return false;
+            };
+            let dot_dir = e.file_type().is_dir() && e.file_name().to_string_lossy().starts_with('.');
+            !dot_dir && !ignored.matches(relative)
+        })
+        .flatten()
+        .filter(|e| !e.file_type().is_dir())
+        .filter_map(|e| e.path().strip_prefix(root).ok().map(Path::to_path_buf))
+        .take(LIMIT)
+        .collect()
+}
+
+/// Indexes into `files` that match `query`, best first.
+pub fn matches(files: &[PathBuf], query: &str) -> Vec<usize> {
+    let mut matches: Vec<(i64, usize)> = files
+        .iter()
+        .enumerate()
+        .filter_map(|(i, file)| fuzzy::score(query, &file.to_string_lossy(), false).map(|score| (score, i)))
+        .collect();
+    // Stable, so equal scores keep walk order.
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    matches.into_iter().map(|(_, i)| i).collect()
+}
+
+/// The patterns of a `.gitignore`, roughly: a pattern with a slash in the
+/// middle matches a path from the root, any other matches a name at any
+/// depth. Negations (`!`) aren't supported and are left out.
+struct Ignored {
+    names: GlobSet,
+    paths: GlobSet,
+}
+
+impl Ignored {
+    fn read(root: &Path) -> Ignored {
+        let mut names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        let text = fs::read_to_string(root.join(".gitignore")).unwrap_or_default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+            let pattern = line.trim_end_matches('/');
+            let (builder, pattern) = match pattern.strip_prefix('/') {
+                Some(anchored) => (&mut paths, anchored),
+                None if pattern.contains('/') => (&mut paths, pattern),
+                None => (&mut names, pattern),
+            };
+            if let Ok(glob) = Glob::new(pattern) {
+                builder.add(glob);
+            }
+        }
+        Ignored {
+            names: names.build().unwrap_or_else(|_| GlobSet::empty()),
+            paths: paths.build().unwrap_or_else(|_| GlobSet::empty()),
+        }
+    }
+
+    fn matches(&self, relative: &Path) -> bool {
+        self.paths.is_match(relative) || relative.file_name().is_some_and(|name| self.names.is_match(name))
+    }
+}
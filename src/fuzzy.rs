@@ -0,0 +1,39 @@
+/// Scores `candidate` against `query` as a subsequence match, ignoring
+/// case unless `case_sensitive`, or returns `None` if the query's
+/// characters don't all appear in order. Higher is better: consecutive
+/// runs and matches at the start of a word are rewarded, gaps are
+/// penalised.
+pub fn score(query: &str, candidate: &str, case_sensitive: bool) -> Option<i64> {
+    if query.is_empty() {
+        return Some(0);
+    }
+    let fold = |c: char| if case_sensitive { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let query: Vec<char> = query.chars().map(fold).collect();
+    let mut qi = 0;
+    let mut score = 0;
+    let mut prev_match: Option<usize> = None;
+    let mut prev_char: Option<char> = None;
+
+    for (ci, c) in candidate.chars().enumerate() {
+        if qi == query.len() {
+            break;
+        }
+        if fold(c) == query[qi] {
+            score += 1;
+            if prev_match.is_some_and(|p| p + 1 == ci) {
+                score += 5;
+            }
+            if !prev_char.is_some_and(char::is_alphanumeric) {
+                score += 3;
+            }
+            if let Some(p) = prev_match {
+                score -= (ci - p - 1).min(5) as i64;
+            }
+            prev_match = Some(ci);
+            qi += 1;
+        }
+        prev_char = Some(c);
+    }
+
+    (qi == query.len()).then_some(score)
+}
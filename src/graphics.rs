@@ -0,0 +1,50 @@
+use std::{
+    io::{self, stdout, Write},
+    path::Path,
+};
+use ratatui::layout::Rect;
+use viuer::KittySupport;
+
+/// Extensions drawn as pictures rather than read as text.
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "bmp", "webp", "ico", "tif", "tiff"];
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+/// True when the terminal speaks the kitty or iTerm graphics protocol.
+/// Asking kitty can mean a round trip to the terminal, so call this once.
+pub fn supported() -> bool {
+    viuer::is_iterm_supported() || viuer::get_kitty_support() != KittySupport::None
+}
+
+/// Draws the image at `path` scaled to fit `area`, straight to the
+/// terminal and outside ratatui's buffer. It stays on screen until
+/// `clear`.
+pub fn draw(path: &Path, area: Rect) -> io::Result<()> {
+    let config = viuer::Config {
+        absolute_offset: true,
+        x: area.x,
+        y: area.y as i16,
+        width: Some(area.width as u32),
+        height: Some(area.height as u32),
+        restore_cursor: true,
+        ..Default::default()
+    };
+    viuer::print_from_file(path, &config).map_err(io::Error::other)?;
+    Ok(())
+}
+
+/// Removes images drawn with `draw`. kitty keeps them on a layer of their
+/// own, so they need deleting explicitly; iTerm images live in the cells
+/// and go once the screen is repainted.
+pub fn clear() -> io::Result<()> {
+    if viuer::get_kitty_support() != KittySupport::None {
+        let mut out = stdout();
+        out.write_all(b"\x1b_Ga=d\x1b\\")?;
+        out.flush()?;
+    }
+    Ok(())
+}
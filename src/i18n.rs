@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    env,
+    fmt::Display,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
+use crate::PathExt;
+
+/// Every piece of UI text, by key, in English. English needs no catalog
+/// file, and anything a translation leaves out falls back to this.
+///
+/// `{name}`-style placeholders are filled in by `trf`; translations may
+/// put them in any order.
+const ENGLISH: &[(&str, &str)] = &[
+    // Shortcuts bar
+    ("key_switch_tabs", "Switch Tabs"),
+    ("key_tab_by_number", "Tab By Number"),
+    ("key_all_projects", "All Projects"),
+    ("key_home", "Home Project"),
+    ("key_cycle_tab", "Prev/Next Project"),
+    ("key_tab_switcher", "Tab Switcher"),
+    ("key_side_tabs", "Tabs On Side"),
+    ("key_find_tab", "Find Tab"),
+    ("key_find_file", "Find File"),
+    ("key_recent", "Recent Files"),
+    ("key_add_tab", "Add As Tab"),
+    ("key_note", "Project Note"),
+    ("key_listing", "Show/Hide Listing"),
+    ("key_navigate", "Navigate"),
+    ("key_select", "Select"),
+    ("key_up", "Up"),
+    ("key_root", "Tab Root"),
+    ("key_root_here", "Make Root"),
+    ("key_history", "Back/Forward"),
+    ("key_move_tab", "Move Tab"),
+    ("key_confirm", "Confirm"),
+    ("key_command", "Run Command"),
+    ("key_task", "Run Task"),
+    ("key_go_to", "Go To Path"),
+    ("key_path_display", "Path Display"),
+    ("key_hide_tab", "Hide/Unhide Tab"),
+    ("key_filter", "Filter"),
+    ("key_sort", "Sort/Reverse"),
+    ("key_dotfiles", "Dotfiles"),
+    ("key_pin", "Pin"),
+    ("key_open_with", "Open With Default App"),
+    ("key_open_dir", "Open Directory In Editor"),
+    ("key_format", "Format File"),
+    ("key_diff_tool", "Diff/Merge"),
+    ("key_disk_usage", "Disk Usage"),
+    ("key_archive", "Compress/Extract"),
+    ("key_split", "Split/Switch Focus"),
+    ("key_mark", "Mark"),
+    ("key_transfer", "Copy/Move To Other Pane"),
+    ("key_copy_to_tab", "Copy To Project"),
+    ("key_export", "Export Listing"),
+    ("key_undo", "Undo Copy/Move"),
+    ("key_preview", "Preview/Outline/Diff"),
+    ("key_scroll_preview", "Scroll Preview"),
+    ("key_config", "Config"),
+    ("key_quit", "Quit"),
+    // Prompts
+    ("prompt_go_to", "cd "),
+    ("prompt_filter_case", "/ (Aa) "),
+    ("prompt_tab_number", "tab "),
+    ("prompt_new_tab", "new tab: "),
+    ("prompt_new_project", "new project: "),
+    ("prompt_export", "export to (empty for clipboard): "),
+    // Confirmations
+    ("confirm_open", "Open {name} in {editor}?"),
+    ("confirm_reopen", "Already opened {name}, open again?"),
+    ("confirm_extract", "Extract {name} here?"),
+    ("confirm_overwrite", "Overwrite {name}?"),
+    ("confirm_keys", "(y/n)"),
+    ("confirm_keys_conflict", "(y: overwrite, n: skip, r: rename)"),
+    ("name_and_more", "{name} and {count} more"),
+    // Listing and status bar
+    ("all_projects", "All Projects"),
+    ("no_projects", "No projects yet — press a to create one"),
+    ("contents_of", "Contents of {path}"),
+    ("dotfiles_hidden", "[dotfiles hidden]"),
+    ("sort_modifier", "[sort:{mode}{arrow}]"),
+    ("filter_modifier", "[filter:{filter}]"),
+    ("filter_modifier_case", "[filter:{filter}, case-sensitive]"),
+    ("marked_modifier", "[{count} marked]"),
+    ("sort_name", "name"),
+    ("sort_size", "size"),
+    ("sort_modified", "modified"),
+    ("path_projects", "projects"),
+    ("path_tab", "tab"),
+    ("path_abs", "abs"),
+    ("non_utf8", "[non-UTF-8]"),
+    ("broken_link", "[broken link]"),
+    ("free_space", "{size} free"),
+    ("calculating", "calculating…"),
+    ("size_unknown", "size unknown: {error}"),
+    // Preview
+    ("preview", "Preview"),
+    ("preview_loading", "Preview (loading…)"),
+    ("preview_no_outline", "Preview (no outline for this file type)"),
+    ("preview_diff", "Changes since the last commit (D: whole file)"),
+    ("preview_no_diff", "Preview (no uncommitted changes)"),
+    ("outline", "Outline ([/]: select, Enter: open there)"),
+    ("no_definitions", "No top-level definitions"),
+    ("binary_file", "Binary file"),
+    ("empty_dir", "Empty directory"),
+    ("image_info", "{kind} image, {width} × {height} px"),
+    ("cant_preview", "Can't preview: {error}"),
+    // Popups
+    ("select_editor", "Select Editor"),
+    ("cant_start", "Can't start"),
+    ("projects_not_dir", "{path} is not a directory"),
+    ("no_projects_dir", "{path} doesn't exist"),
+    ("projects_dir_help", "bod shows each directory inside the projects directory as a tab. Set projects_dir in ~/.config/bod/config.toml, or create the directory, then start bod again."),
+    ("press_key_to_quit", "Press any key to quit"),
+    ("setup_title", "Set up bod (Esc: skip)"),
+    ("setup_intro", "There's no config yet. Answer three questions and bod writes them to ~/.config/bod/config.toml, where the rest of the settings are too."),
+    ("setup_projects_dir", "Which directory holds your projects? Each directory in it gets a tab."),
+    ("setup_editor", "What command should files open in?"),
+    ("setup_theme", "Which colour theme?"),
+    ("setup_keys_input", "Enter: next, Tab: complete"),
+    ("setup_keys_list", "Up/Down: choose, Enter: finish"),
+    ("welcome", "Welcome to bod"),
+    ("welcome_projects", "{count} projects in {path}"),
+    ("welcome_switch_project", "switch project"),
+    ("welcome_tab_number", "switch by tab number"),
+    ("welcome_switch_letter", "switch by letter"),
+    ("welcome_open", "open"),
+    ("welcome_listing", "show/hide the listing"),
+    ("welcome_config", "edit the config"),
+    ("welcome_start", "Press any key to start"),
+    ("hidden_tabs", "Hidden tabs (Enter: unhide, Esc: close)"),
+    ("no_hidden_tabs", "No hidden tabs"),
+    ("switch_tab", "Switch tab (Esc: cancel)"),
+    ("copy_to_tab", "Copy into which tab? (Esc: cancel)"),
+    ("recent_files", "Recently modified (Enter: go to, r: rescan, Esc: close)"),
+    ("no_recent_files", "No files found"),
+    ("find_tab", "Find tab: "),
+    ("find_file", "Find in {tab} (Ctrl+R: rescan): "),
+    ("finder_needs_tab", "The file finder searches a tab; open one first"),
+    ("note", "Note: {name} (e: edit, Esc: close)"),
+    ("no_note", "No note yet. Press e to write one."),
+    ("running", "running…"),
+    ("exit_code", "exit {code}"),
+    ("killed", "killed by signal"),
+    ("failed", "failed: {error}"),
+    // Status messages
+    ("cant_pin", "Can't pin {name}: name isn't valid UTF-8"),
+    ("tab_skipped_not_utf8", "No tab for {name}: name isn't valid UTF-8"),
+    ("cant_save_pins", "Couldn't save pins: {error}"),
+    ("cant_open", "Couldn't open {name}: {error}"),
+    ("broken_link_target", "{name} is a broken link: {target} doesn't exist"),
+    ("cant_draw_image", "Couldn't draw image: {error}"),
+    ("no_task", "No task for {name}; set one under [tasks] in the config"),
+    ("archive_running", "An archive job is already running"),
+    ("no_second_pane", "Press | to open a second pane first"),
+    ("other_pane_not_dir", "The other pane isn't showing a directory"),
+    ("copied", "Copied {name} to {path}"),
+    ("moved", "Moved {name} to {path}"),
+    ("copied_many", "Copied {count} items to {path}"),
+    ("moved_many", "Moved {count} items to {path}"),
+    ("cant_copy", "Couldn't copy {name}: {error}"),
+    ("skipped_all", "Skipped {count} already there"),
+    ("cant_move", "Couldn't move {name}: {error}"),
+    ("confirmation_timed_out", "No answer in time; cancelled"),
+    ("nothing_to_undo", "Nothing to undo"),
+    ("undid_copy", "Undid copy: removed {path}"),
+    ("undid_overwrite", "Undid copy: put back the old {path}"),
+    ("undid_move", "Undid move: {path} is back"),
+    ("undid_many", "Undid {count} copies or moves"),
+    ("cant_undo", "Couldn't undo: {error}"),
+    ("not_archive", "{name} isn't a .tar, .tar.gz, .tgz or .zip archive"),
+    ("compressing", "Compressing {name}"),
+    ("extracting", "Extracting {name}"),
+    ("cant_read_archive", "Couldn't read {name}: {error}"),
+    ("cant_list", "Can't list {path}: {error}"),
+    ("archive_read_only", "Inside an archive: read-only"),
+    ("job_wrote", "{job}: wrote {name}"),
+    ("job_failed", "{job} failed: {error}"),
+    ("cant_open_on_startup", "Couldn't open {name} on startup: {error}"),
+    ("no_startup_tab", "on_startup: no tab named {name}"),
+    ("no_default_project", "default_project: no tab named {name}, went to the first tab"),
+    ("startup_exited", "Startup command exited with {code}"),
+    ("startup_killed", "Startup command killed by a signal"),
+    ("startup_failed", "Startup command failed: {error}"),
+    ("action_done", "Ran {command}"),
+    ("no_formatter", "No formatter for {name}"),
+    ("no_changes", "{name} has no changes in git"),
+    ("cant_diff", "Couldn't open {name} in the diff tool: {error}"),
+    ("action_exited", "{command} exited with {code}"),
+    ("action_killed", "{command} killed by a signal"),
+    ("action_failed", "{command} failed: {error}"),
+    ("opened", "Opened {name}"),
+    ("opened_with", "Opened {name} with {handler}"),
+    ("default_app", "the default app"),
+    ("cant_go_to", "Can't go to {path}: {error}"),
+    ("exported", "Exported the listing to {path}"),
+    ("exported_clipboard", "Copied the listing to the clipboard"),
+    ("cant_export", "Can't export the listing: {error}"),
+    ("no_tab", "No tab {name}"),
+    ("bad_project_name", "{name} isn't a valid project name"),
+    ("cant_create_project", "Couldn't create {name}: {error}"),
+    ("tab_exists", "There's already a tab called {name}"),
+    ("tab_not_utf8", "Can't add a tab for a path that isn't valid UTF-8"),
+    ("cant_save_tab", "Couldn't save tab {name}: {error}"),
+    ("cant_save_tab_order", "Couldn't save tab order: {error}"),
+    ("hid_tab", "Hid {name} (U to manage hidden tabs)"),
+    ("restored_tab", "Restored {name}"),
+    ("cant_open_config", "Couldn't open config: {error}"),
+    ("config_reloaded", "Config reloaded"),
+    ("action_key_taken", "The [[actions]] entry on {key} never runs: bod uses that key"),
+    ("config_not_reloaded", "Config not reloaded: {error}"),
+    ("cant_save_hidden_tabs", "Couldn't save hidden tabs: {error}"),
+    ("cant_read_note", "Couldn't read note: {error}"),
+    ("cant_open_note", "Couldn't open note: {error}"),
+    ("catalog_not_loaded", "Translations not loaded: {error}"),
+];
+
+/// The loaded translation, if the locale has one.
+static CATALOG: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+/// Loads the catalog for the configured locale, else the one the
+/// environment asks for messages in (`LC_ALL`, `LC_MESSAGES`, then `LANG`).
+/// Catalogs are TOML files of `key = "text"` in `~/.config/bod/locales`,
+/// named after the full locale (`pt_BR.toml`) or just the language
+/// (`pt.toml`). No catalog means English.
+pub fn load(configured: Option<&str>) -> io::Result<()> {
+    let catalog = match catalog_path(configured)? {
+        Some(path) => {
+            let text = fs::read_to_string(&path)?;
+            let catalog = toml::from_str(&text).map_err(|e| {
+                io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e))
+            })?;
+            Some(catalog)
+        },
+        None => None,
+    };
+    *CATALOG.write().unwrap_or_else(|e| e.into_inner()) = catalog;
+    Ok(())
+}
+
+fn catalog_path(configured: Option<&str>) -> io::Result<Option<PathBuf>> {
+    let from_env = || {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+    };
+    let Some(locale) = configured.map(str::to_owned).or_else(from_env) else {
+        return Ok(None);
+    };
+    let locale = locale.split(['.', '@']).next().unwrap_or_default();
+    let language = locale.split('_').next().unwrap_or_default();
+    let dir = Path::new("~/.config/bod/locales").expand_home()?;
+    let path = [locale, language]
+        .into_iter()
+        .filter(|name| !name.is_empty())
+        .map(|name| dir.join(format!("{}.toml", name)))
+        .find(|path| path.is_file());
+    Ok(path)
+}
+
+/// The text for `key` in the current language.
+pub fn tr(key: &str) -> String {
+    let catalog = CATALOG.read().unwrap_or_else(|e| e.into_inner());
+    if let Some(text) = catalog.as_ref().and_then(|c| c.get(key)) {
+        return text.clone();
+    }
+    ENGLISH.iter()
+        .find(|(k, _)| *k == key)
+        .map_or_else(|| key.to_string(), |(_, text)| text.to_string())
+}
+
+/// `tr` with each `{name}` placeholder replaced by its value from `args`.
+pub fn trf(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    fill(&tr(key), args)
+}
+
+/// Replaces each `{name}` in `template` with its value from `args`.
+pub fn fill(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = template.to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
@@ -0,0 +1,340 @@
+use std::{
+    env,
+    ffi::OsString,
+    io::{self, stdout, Stdout},
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+    thread,
+    time::Duration,
+};
+use ratatui::{backend::CrosstermBackend, Terminal};
+use crossterm::{
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    ExecutableCommand,
+};
+
+use crate::{
+    config::{Config, LaunchFocus, LaunchMode},
+    shell,
+};
+
+pub type Tui = Terminal<CrosstermBackend<Stdout>>;
+
+/// The `editor` command split into words, `nvim` when unset, followed by
+/// any `[editor_args]` for `project`.
+fn editor_command<'a>(config: &'a Config, project: Option<&str>) -> Vec<&'a str> {
+    let mut words = match config.editor.as_deref().map(str::split_whitespace) {
+        Some(words) if words.clone().next().is_some() => words.collect(),
+        _ => vec!["nvim"],
+    };
+    words.extend(project_args(config, project).split_whitespace());
+    words
+}
+
+/// The `[editor_args]` entry for `project`, or nothing.
+fn project_args<'a>(config: &'a Config, project: Option<&str>) -> &'a str {
+    project.and_then(|p| config.editor_args.get(p)).map_or("", String::as_str)
+}
+
+/// What `open_in_editor` opens files in, as shown to the user: the
+/// program's name, or Neovim when none is configured.
+pub fn editor_name(config: &Config) -> String {
+    match config.editor.as_deref().and_then(|e| e.split_whitespace().next()) {
+        Some(program) => program.to_string(),
+        None => "Neovim".to_string(),
+    }
+}
+
+/// Opens `path` in the editor, either in a new terminal window or in this
+/// one, with the cursor on `line` when given. `open_file_cmd` replaces the
+/// usual `editor +LINE path` when set. `project` picks the
+/// `[editor_args]` to add, if any.
+pub fn open_in_editor(terminal: &mut Tui, config: &Config, project: Option<&str>, path: &Path, line: Option<usize>) -> io::Result<()> {
+    log::info!("opening {} in the editor", path.display());
+    let argv = match &config.open_file_cmd {
+        Some(template) => shell_argv(template, project_args(config, project), path, line),
+        None => {
+            let mut argv: Vec<OsString> = editor_command(config, project).into_iter().map(OsString::from).collect();
+            argv.extend(line.map(|line| format!("+{}", line).into()));
+            argv.push(path.into());
+            argv
+        },
+    };
+    launch(terminal, config, &argv, None)
+}
+
+/// Opens the directory `path` in the editor as a workspace, with
+/// `open_dir_cmd`, or else by starting the editor on it from inside it.
+pub fn open_dir_in_editor(terminal: &mut Tui, config: &Config, project: Option<&str>, path: &Path) -> io::Result<()> {
+    log::info!("opening directory {} in the editor", path.display());
+    if let Some(template) = config.open_dir_cmd.as_deref() {
+        return launch(terminal, config, &shell_argv(template, project_args(config, project), path, None), None);
+    }
+    // Run directly rather than through sh, so a name that isn't UTF-8
+    // reaches the editor intact.
+    let mut argv: Vec<OsString> = editor_command(config, project).into_iter().map(OsString::from).collect();
+    argv.push(path.into());
+    launch(terminal, config, &argv, Some(path))
+}
+
+/// How git sees a file being opened in a diff tool.
+#[derive(Clone, Copy)]
+pub enum GitChange {
+    /// Changed since the last commit.
+    Modified,
+    /// Left with conflicts by a merge or rebase.
+    Conflicted,
+}
+
+/// What git says about `path`. `None` when it's unchanged or untracked,
+/// isn't in a repository, or git isn't installed.
+pub fn git_change(path: &Path) -> Option<GitChange> {
+    let (dir, name) = (path.parent()?, path.file_name()?);
+    let output = Command::new("git")
+        .args(["status", "--porcelain", "--"])
+        .arg(name)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    match output.stdout.get(..2)? {
+        b"??" | b"!!" => None,
+        b"DD" | b"AU" | b"UD" | b"UA" | b"DU" | b"AA" | b"UU" => Some(GitChange::Conflicted),
+        _ => Some(GitChange::Modified),
+    }
+}
+
+/// Opens `path` in `diff_cmd`, launched the way the editor is. When that's
+/// unset, a Vim or Neovim editor is started through `git difftool`, or
+/// `git mergetool` for a file with conflicts.
+pub fn open_in_diff_tool(terminal: &mut Tui, config: &Config, path: &Path, change: GitChange) -> io::Result<()> {
+    log::info!("opening {} in the diff tool", path.display());
+    if let Some(template) = config.diff_cmd.as_deref() {
+        return launch(terminal, config, &shell_argv(template, "", path, None), None);
+    }
+    // git has to run from inside the repository.
+    let dir = path.parent().unwrap_or(Path::new("."));
+    launch(terminal, config, &default_diff_argv(config, path, change)?, Some(dir))
+}
+
+/// The `git difftool` or `git mergetool` command for the editor's own diff
+/// mode, if it's one git knows, to run from `path`'s directory.
+fn default_diff_argv(config: &Config, path: &Path, change: GitChange) -> io::Result<Vec<OsString>> {
+    let editor = editor_command(config, None)[0];
+    let tool = match Path::new(editor).file_name().and_then(|name| name.to_str()) {
+        Some("nvim") => "nvimdiff",
+        Some("vim") => "vimdiff",
+        _ => {
+            let message = format!("diff_cmd isn't set and {} has no diff mode bod knows", editor);
+            return Err(io::Error::new(io::ErrorKind::Unsupported, message));
+        },
+    };
+    let git = match change {
+        GitChange::Modified => "difftool",
+        GitChange::Conflicted => "mergetool",
+    };
+    let name = path.file_name().unwrap_or(path.as_os_str());
+    Ok(vec!["git".into(), git.into(), "--no-prompt".into(), format!("--tool={}", tool).into(), "--".into(), name.into()])
+}
+
+/// A command template as an `sh -c` invocation, with `{path}` quoted,
+/// `{args}` replaced by `args` as written and `{line}` filled in (1 when
+/// there's no line to go to).
+fn shell_argv(template: &str, args: &str, path: &Path, line: Option<usize>) -> Vec<OsString> {
+    let command = template
+        .replace("{path}", &shell::quote(&path.to_string_lossy()))
+        .replace("{args}", args)
+        .replace("{line}", &line.unwrap_or(1).to_string());
+    vec!["sh".into(), "-c".into(), command.into()]
+}
+
+/// Runs `argv` the way `launch_mode` says: in a new alacritty window, or in
+/// this terminal with the TUI suspended. `dir` is where it starts, when it
+/// matters.
+fn launch(terminal: &mut Tui, config: &Config, argv: &[OsString], dir: Option<&Path>) -> io::Result<()> {
+    let mut command = match config.launch_mode {
+        LaunchMode::NewWindow => {
+            let mut command = Command::new("alacritty");
+            command.arg("-e").args(argv);
+            command
+        },
+        LaunchMode::Foreground => {
+            let mut command = Command::new(&argv[0]);
+            command.args(&argv[1..]);
+            command
+        },
+    };
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    match config.launch_mode {
+        LaunchMode::NewWindow => {
+            let child = command.spawn()?;
+            focus_after_launch(config.launch_focus, child.id());
+        },
+        LaunchMode::Foreground => {
+            run_foreground(terminal, &mut command)?;
+        },
+    }
+    Ok(())
+}
+
+/// What `open_file` ended up opening a file with.
+pub enum Handler {
+    /// A program from `[openers]` or the editor, by name.
+    Program(String),
+    /// Whatever the OS associates with the file.
+    DefaultApp,
+}
+
+/// Opens `path` with the first handler that's available: the `[openers]`
+/// command for its extension, then the editor, then the OS default. A step
+/// is skipped when its program isn't installed or fails to start.
+pub fn open_file(terminal: &mut Tui, config: &Config, project: Option<&str>, path: &Path, line: Option<usize>) -> io::Result<Handler> {
+    let extension = path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+    let opener = extension.and_then(|e| config.openers.get(&e));
+    if let Some(words) = opener.map(|o| o.split_whitespace().collect::<Vec<_>>()) {
+        if let Some(program) = words.first().filter(|p| program_exists(p)) {
+            let spawned = Command::new(program)
+                .args(&words[1..])
+                .arg(path)
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn();
+            match spawned {
+                Ok(_) => return Ok(Handler::Program(program.to_string())),
+                Err(e) => log::warn!("opener {} failed: {}", program, e),
+            }
+        }
+    }
+    let terminal_ok = matches!(config.launch_mode, LaunchMode::Foreground) || program_exists("alacritty");
+    // A template runs through sh, so there's no one program to look for.
+    let editor_ok = config.open_file_cmd.is_some() || program_exists(editor_command(config, project)[0]);
+    if terminal_ok && editor_ok {
+        match open_in_editor(terminal, config, project, path, line) {
+            Ok(()) => return Ok(Handler::Program(editor_name(config))),
+            Err(e) => log::warn!("editor failed: {}", e),
+        }
+    }
+    open_with_default_app(path)?;
+    Ok(Handler::DefaultApp)
+}
+
+/// True if `program` is a path to a file, or a file in some `$PATH`
+/// directory.
+pub fn program_exists(program: &str) -> bool {
+    if program.contains(std::path::MAIN_SEPARATOR) {
+        return Path::new(program).is_file();
+    }
+    env::var_os("PATH").is_some_and(|paths| env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
+
+/// Moves focus to the window `focus` asks for once a new editor window has
+/// had time to appear. Strictly best effort: without the helper tool
+/// (`wmctrl`/`xdotool` on X11, `osascript` on macOS) nothing happens.
+fn focus_after_launch(focus: LaunchFocus, editor_pid: u32) {
+    let command = match focus {
+        LaunchFocus::Default => return,
+        LaunchFocus::Tui => raise_tui_command(),
+        LaunchFocus::Editor => raise_editor_command(editor_pid),
+    };
+    let Some(mut command) = command else {
+        return;
+    };
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null());
+    thread::spawn(move || {
+        // Let the new window map and take focus before taking it back.
+        thread::sleep(Duration::from_millis(500));
+        if let Err(e) = command.status() {
+            log::warn!("couldn't move focus with {:?}: {}", command.get_program(), e);
+        }
+    });
+}
+
+/// Raises the terminal this TUI is running in, found through `$WINDOWID`
+/// on X11 and `$TERM_PROGRAM` on macOS.
+fn raise_tui_command() -> Option<Command> {
+    if cfg!(target_os = "macos") {
+        let app = match env::var("TERM_PROGRAM").ok()?.as_str() {
+            "Apple_Terminal" => "Terminal".to_string(),
+            other => other.trim_end_matches(".app").to_string(),
+        };
+        Some(osascript_activate(&app))
+    } else if cfg!(unix) {
+        let window = env::var("WINDOWID").ok()?;
+        let mut command = Command::new("wmctrl");
+        command.args(["-i", "-a", &window]);
+        Some(command)
+    } else {
+        None
+    }
+}
+
+/// Raises the editor window, found by the pid of the terminal it runs in.
+fn raise_editor_command(pid: u32) -> Option<Command> {
+    if cfg!(target_os = "macos") {
+        Some(osascript_activate("Alacritty"))
+    } else if cfg!(unix) {
+        let mut command = Command::new("xdotool");
+        command.args(["search", "--sync", "--pid", &pid.to_string(), "windowactivate"]);
+        Some(command)
+    } else {
+        None
+    }
+}
+
+fn osascript_activate(app: &str) -> Command {
+    let mut command = Command::new("osascript");
+    command.args(["-e", &format!("tell application \"{}\" to activate", app)]);
+    command
+}
+
+/// Opens `path` with whatever the OS associates with it: `xdg-open` on
+/// Linux and the BSDs, `open` on macOS and `start` on Windows.
+pub fn open_with_default_app(path: &Path) -> io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        // The empty argument is the window title `start` would otherwise
+        // take from a quoted path.
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    log::info!("opening {} with {:?}", path.display(), command.get_program());
+    // The opener's chatter would otherwise scribble over the TUI.
+    command
+        .arg(path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    Ok(())
+}
+
+/// Hands the terminal to `command` until it exits, then takes it back.
+///
+/// The TUI is restored even if the command fails to start, so an error
+/// never leaves the user stuck outside the alternate screen.
+pub fn run_foreground(terminal: &mut Tui, command: &mut Command) -> io::Result<ExitStatus> {
+    disable_raw_mode()?;
+    stdout().execute(LeaveAlternateScreen)?;
+
+    let status = command.status();
+    match &status {
+        Ok(status) => log::info!("{:?} exited with {}", command.get_program(), status),
+        Err(e) => log::error!("couldn't run {:?}: {}", command.get_program(), e),
+    }
+
+    enable_raw_mode()?;
+    stdout().execute(EnterAlternateScreen)?;
+    terminal.clear()?;
+    status
+}
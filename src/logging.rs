@@ -0,0 +1,66 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Mutex,
+};
+use log::{LevelFilter, Log, Metadata, Record};
+
+use crate::PathExt;
+
+/// Appends log records to a file, one line each.
+struct FileLogger {
+    file: Mutex<File>,
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f");
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        let _ = writeln!(file, "{} {:<5} {}: {}", now, record.level(), record.target(), record.args());
+    }
+
+    fn flush(&self) {
+        let _ = self.file.lock().unwrap_or_else(|e| e.into_inner()).flush();
+    }
+}
+
+/// Where the log goes: `bod/bod.log` in the state directory, which is
+/// `~/.local/state` on Linux and the cache directory elsewhere.
+pub fn path() -> io::Result<PathBuf> {
+    let dir = match dirs::state_dir().or_else(dirs::cache_dir) {
+        Some(dir) => dir,
+        None => Path::new("~/.local/state").expand_home()?,
+    };
+    Ok(dir.join("bod").join("bod.log"))
+}
+
+/// Starts logging at the level `BOD_LOG` names (`error` through `trace`).
+/// Without it nothing is set up, and the log macros are left checking a
+/// level that's off.
+pub fn init() -> io::Result<()> {
+    let Some(level) = env::var("BOD_LOG").ok().filter(|level| !level.is_empty()) else {
+        return Ok(());
+    };
+    let level = LevelFilter::from_str(&level).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("BOD_LOG: unknown level {:?}", level))
+    })?;
+    let path = path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    log::set_boxed_logger(Box::new(FileLogger { file: Mutex::new(file) })).map_err(io::Error::other)?;
+    log::set_max_level(level);
+    log::info!("bod {} started", env!("CARGO_PKG_VERSION"));
+    Ok(())
+}
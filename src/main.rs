@@ -1,108 +1,2881 @@
 use std::{
+    borrow::Cow,
+    cell::Cell,
+    collections::{BTreeSet, HashMap},
+    ffi::{OsStr, OsString},
     io::{self, stdout},
     fs,
-    path::Path,
-    process::Command,
-    time::Duration,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
 };
 use ratatui::{
     backend::CrosstermBackend,
-    widgets::{Block, Borders, Tabs, Paragraph, List, ListItem},
-    layout::{Layout, Direction, Constraint},
-    style::{Style, Color, Modifier},
+    widgets::{Block, Borders, Clear, Tabs, Padding, Paragraph, List, ListItem, ListState, Wrap},
+    layout::{Alignment, Layout, Direction, Constraint, Rect},
+    style::{Modifier, Style},
     text::{Line, Span},
     Terminal,
 };
 use crossterm::{
-    event::{self, Event, KeyCode},
+    event::{self, DisableFocusChange, EnableFocusChange, Event, KeyCode, KeyEvent, KeyModifiers},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     ExecutableCommand,
 };
 use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+mod archive;
+mod clock;
+mod complete;
+mod config;
+mod export;
+mod fileops;
+mod finder;
+mod fuzzy;
+mod graphics;
+mod i18n;
+mod launch;
+mod logging;
+mod manifest;
+mod notes;
+mod preview;
+mod recent;
+mod session;
+mod setup;
+mod shell;
+mod theme;
+mod usage;
+mod visits;
+mod width;
+
+use archive::{ArchiveFormat, ArchiveJob, JobStatus};
+use clock::Clock;
+use complete::Completion;
+use config::{Config, ConfigWatcher, EnterWithoutSelection, LaunchMode, OnConflict, PopupArea, StartPopup, StartView, TabHighlight, TabOrder, TabPosition};
+use fileops::{Conflict, Operation, UNDO_DEPTH};
+use launch::Handler;
+use manifest::ProjectInfo;
+use preview::{DirChild, Preview, PreviewBody};
+use recent::RecentFile;
+use session::Session;
+use shell::{CommandOutput, CommandStatus};
+use theme::{ColorRules, Theme};
+use usage::{DiskUsage, Usage};
+use visits::Visits;
 
 struct App {
+    config: Config,
+    theme: Theme,
+    clock: Clock,
+    base_path: PathBuf,
     tabs: Vec<String>,
-    current_tab: usize,
+    /// Manifest details per tab name, for tabs that have a manifest.
+    manifests: HashMap<String, ProjectInfo>,
+    /// The subdirectory last visited in each tab, by tab name, restored on
+    /// switching back when `remember_tab_paths` is set.
+    tab_subpaths: HashMap<String, PathBuf>,
+    /// The pane keys act on.
+    pane: Pane,
+    /// The second pane in dual-pane mode. Switching focus swaps it with
+    /// `pane`.
+    other_pane: Option<Pane>,
+    /// Whether `pane` is the one drawn on the left.
+    pane_on_left: bool,
+    /// Leave the main loop before the next frame; see `quit_after_open`.
+    quit: bool,
     show_content: bool,
     show_editor_selection: bool,
+    show_welcome: bool,
     selected_editor: usize,
+    confirmation: Option<Confirmation>,
+    /// When `confirmation` was put up, for `confirm_timeout_secs`.
+    confirmation_asked: Instant,
+    /// A copy or move waiting on `Confirmation::Overwrite`.
+    pending_transfer: Option<PendingTransfer>,
+    /// Editor launches still inside the `reopen_confirm_secs` window.
+    recent_launches: Vec<(PathBuf, Instant)>,
+    input: Option<Input>,
+    command_output: Option<CommandOutput>,
+    /// The `on_startup` command, running unseen until it finishes.
+    startup_command: Option<CommandOutput>,
+    /// An `[[actions]]` command running without its output on screen.
+    action_command: Option<CommandOutput>,
+    /// Refresh once the command in `command_output` exits; set for actions.
+    refresh_after_output: bool,
+    disk_usage: DiskUsage,
+    archive_job: Option<ArchiveJob>,
+    last_refresh: Instant,
+    /// Free bytes on the projects directory's filesystem, for
+    /// `show_free_space`. `None` until known or if it can't be found out.
+    free_space: Option<u64>,
+    free_space_checked: Option<Instant>,
+    /// The session as last written, so `autosave_session` only writes
+    /// when something changed.
+    saved_session: Session,
+    last_session_check: Instant,
+    visits: Visits,
+    /// `visits` has changes not yet written.
+    visits_changed: bool,
+    /// Tabs by frecency as of startup, for `tab_order = "frecency"`.
+    frecent_tabs: Vec<String>,
+    /// When the last terminal event arrived, for `idle_dim_secs`.
+    last_input: Instant,
+    path_display: PathDisplay,
+    /// One-off feedback shown in the status bar until the next keypress.
+    status_message: Option<String>,
+    /// Selected row of the hidden-tabs popup, when it's open.
+    hidden_tabs_popup: Option<usize>,
+    /// The single-key tab switcher overlay, and what picking a tab in it
+    /// does.
+    tab_switcher: Option<TabPick>,
+    tab_search: Option<TabSearch>,
+    recent: Option<RecentView>,
+    finder: Option<FileFinder>,
+    /// Files under each tab as the finder last found them, relative to the
+    /// tab root. Ctrl+R in the finder walks the tab again.
+    finder_files: HashMap<String, Vec<PathBuf>>,
+    /// Text of the current project's note while its popup is open.
+    note_popup: Option<String>,
+    /// Set while the config is open in a detached editor window.
+    config_reload_pending: bool,
+    config_watcher: Option<ConfigWatcher>,
+    show_hidden: bool,
+    show_preview: bool,
+    /// The selected file's preview, reloaded when the selection changes.
+    preview: Option<Preview>,
+    /// First preview line shown, reset when the previewed file changes.
+    preview_scroll: usize,
+    /// Arrow keys scroll the preview instead of moving the selection.
+    preview_focused: bool,
+    /// Tabs are listed down the side rather than across the top.
+    side_tabs: bool,
+    /// Arrow keys move through the side tab list, switching tabs.
+    sidebar_focused: bool,
+    /// `image_previews` is on and the terminal can draw images.
+    graphics: bool,
+    /// Room `render_preview` left for an image this frame.
+    image_area: Cell<Option<Rect>>,
+    /// The image on screen and where, drawn after ratatui's frame.
+    drawn_image: Option<(PathBuf, Rect)>,
+    /// Show the preview as an outline of top-level definitions.
+    outline_mode: bool,
+    outline_selected: usize,
+    /// Show git's diff in the preview for files with uncommitted changes.
+    diff_mode: bool,
+    sort_mode: SortMode,
+    sort_reverse: bool,
+    filter: Option<String>,
+    /// The filter tells upper and lower case apart. Tab flips it while
+    /// typing one.
+    search_case_sensitive: bool,
+    color_rules: ColorRules,
+    /// Copies and moves made this run, most recent last, for Ctrl+Z. Each
+    /// is one keypress's worth, which may cover several marked entries.
+    undo_stack: Vec<Vec<Operation>>,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SortMode {
+    Name,
+    Size,
+    Modified,
+}
+
+impl SortMode {
+    fn next(self) -> SortMode {
+        match self {
+            SortMode::Name => SortMode::Size,
+            SortMode::Size => SortMode::Modified,
+            SortMode::Modified => SortMode::Name,
+        }
+    }
+
+    fn label(self) -> String {
+        i18n::tr(match self {
+            SortMode::Name => "sort_name",
+            SortMode::Size => "sort_size",
+            SortMode::Modified => "sort_modified",
+        })
+    }
+
+    fn compare(self, a: &DirEntry, b: &DirEntry) -> std::cmp::Ordering {
+        match self {
+            SortMode::Name => a.name.cmp(&b.name),
+            SortMode::Size => a.size.cmp(&b.size).then_with(|| a.name.cmp(&b.name)),
+            SortMode::Modified => a.modified.cmp(&b.modified).then_with(|| a.name.cmp(&b.name)),
+        }
+    }
+}
+
+/// How the current location is shown in the list title and status bar.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PathDisplay {
+    /// `project/sub/dir`, relative to the projects directory.
+    Base,
+    /// `/sub/dir`, relative to the current tab's root.
+    Tab,
+    Absolute,
+}
+
+impl PathDisplay {
+    fn next(self) -> PathDisplay {
+        match self {
+            PathDisplay::Base => PathDisplay::Tab,
+            PathDisplay::Tab => PathDisplay::Absolute,
+            PathDisplay::Absolute => PathDisplay::Base,
+        }
+    }
+
+    fn label(self) -> String {
+        i18n::tr(match self {
+            PathDisplay::Base => "path_projects",
+            PathDisplay::Tab => "path_tab",
+            PathDisplay::Absolute => "path_abs",
+        })
+    }
+}
+
+/// A yes/no question shown in a popup. `y` accepts, `n` dismisses.
+///
+/// Each carries the display name of the entry it's about, and the path
+/// acted on, fixed when asked so moving the cursor can't change it.
+enum Confirmation {
+    /// Open the file in the editor.
+    Open(String, PathBuf),
+    /// The file was opened moments ago.
+    Reopen(String, PathBuf),
+    /// Extract the archive.
+    Extract(String, PathBuf),
+    /// Replace entries in the way of `pending_transfer`; n skips them and
+    /// r renames the new ones instead.
+    Overwrite(String),
+}
+
+impl Confirmation {
+    /// Key of this question's template under `[confirmations]`.
+    fn key(&self) -> &'static str {
+        match self {
+            Confirmation::Open(..) => "open",
+            Confirmation::Reopen(..) => "reopen",
+            Confirmation::Extract(..) => "extract",
+            Confirmation::Overwrite(_) => "overwrite",
+        }
+    }
+
+    /// The built-in template, in the current language.
+    fn default_template(&self) -> String {
+        i18n::tr(match self {
+            Confirmation::Open(..) => "confirm_open",
+            Confirmation::Reopen(..) => "confirm_reopen",
+            Confirmation::Extract(..) => "confirm_extract",
+            Confirmation::Overwrite(_) => "confirm_overwrite",
+        })
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Confirmation::Open(name, _)
+            | Confirmation::Reopen(name, _)
+            | Confirmation::Extract(name, _)
+            | Confirmation::Overwrite(name) => name,
+        }
+    }
+
+    /// The question, from the configured template or the built-in one.
+    /// The keys are fixed, so the hint isn't part of the template.
+    fn message(&self, config: &Config) -> String {
+        let template = config.confirmations.get(self.key()).cloned().unwrap_or_else(|| self.default_template());
+        let question = i18n::fill(&template, &[("name", &self.name()), ("editor", &launch::editor_name(config))]);
+        let keys = if let Confirmation::Overwrite(_) = self { "confirm_keys_conflict" } else { "confirm_keys" };
+        format!("{} {}", question, i18n::tr(keys))
+    }
+}
+
+/// A copy or move held back to ask about entries already at `dest_dir`.
+struct PendingTransfer {
+    sources: Vec<PathBuf>,
+    dest_dir: PathBuf,
+    remove_source: bool,
+}
+
+/// What a line of text typed into the input bar will be used for.
+#[derive(Clone, Copy, PartialEq)]
+enum Prompt {
+    Command,
+    Filter,
+    GoTo,
+    /// A tab number, for tabs without a key of their own.
+    TabNumber,
+    /// The label for a new tab from `A`.
+    TabLabel,
+    /// The name of a project directory to create, from the empty state.
+    NewProject,
+    /// Where `X` writes the listing; the clipboard when left empty.
+    Export,
+}
+
+impl Prompt {
+    /// `case_sensitive` says how the filter is matching.
+    fn label(self, case_sensitive: bool) -> String {
+        match self {
+            Prompt::Command => ":".to_string(),
+            Prompt::Filter if case_sensitive => i18n::tr("prompt_filter_case"),
+            Prompt::Filter => "/".to_string(),
+            Prompt::GoTo => i18n::tr("prompt_go_to"),
+            Prompt::TabNumber => i18n::tr("prompt_tab_number"),
+            Prompt::TabLabel => i18n::tr("prompt_new_tab"),
+            Prompt::NewProject => i18n::tr("prompt_new_project"),
+            Prompt::Export => i18n::tr("prompt_export"),
+        }
+    }
+}
+
+/// What picking a tab in the tab switcher does.
+#[derive(Clone, Copy)]
+enum TabPick {
+    Switch,
+    /// Copy the marked entries, or the selected one, into the tab's root.
+    CopyTo,
+}
+
+/// The tab search overlay: a query and the highlighted row of its matches.
+struct TabSearch {
+    query: String,
+    selected: usize,
+}
+
+/// The recent files overlay: what the last scan found and the highlighted
+/// row.
+struct RecentView {
+    files: Vec<RecentFile>,
+    selected: usize,
+}
+
+/// The file finder overlay for one tab: what's typed, the matching files
+/// as indexes into the tab's cached list, best first, and the
+/// highlighted row.
+struct FileFinder {
+    tab: String,
+    query: String,
+    matches: Vec<usize>,
+    selected: usize,
+}
+
+/// A single-line text input shown in place of the shortcuts bar.
+struct Input {
+    prompt: Prompt,
+    text: String,
+    /// Candidates from the last Tab, while they're still relevant.
+    completion: Option<Completion>,
+}
+
+#[derive(Clone)]
+struct DirEntry {
+    /// Display name. May be lossy; never use it to build paths.
+    name: String,
+    /// The real on-disk name, used for every filesystem operation.
+    file_name: OsString,
+    /// True when `file_name` wasn't valid UTF-8 and `name` had to be mangled.
+    lossy: bool,
+    is_dir: bool,
+    size: u64,
+    modified: Option<SystemTime>,
+    /// Number of entries inside a directory; `None` if it couldn't be read.
+    child_count: Option<usize>,
+    /// Listed ahead of everything else; see `Config::pinned`.
+    pinned: bool,
+    /// A symlink whose target doesn't exist.
+    broken_link: bool,
+    /// The `..` row; see `Config::parent_entry`. Stands for no file, so
+    /// nothing that acts on entries takes it.
+    parent: bool,
+}
+
+impl DirEntry {
+    fn new(file_name: OsString, is_dir: bool) -> DirEntry {
+        let (name, lossy) = match file_name.to_str() {
+            Some(name) => (name.to_owned(), false),
+            None => (file_name.to_string_lossy().into_owned(), true),
+        };
+        DirEntry { name, file_name, lossy, is_dir, size: 0, modified: None, child_count: None, pinned: false, broken_link: false, parent: false }
+    }
+
+    fn parent() -> DirEntry {
+        DirEntry { parent: true, ..DirEntry::new(OsString::from(".."), true) }
+    }
+}
+
+/// A single point in the navigation history: a tab (by name, so the entry
+/// survives tabs being reordered) or a free root, and the directory within
+/// it.
+#[derive(Clone, PartialEq)]
+struct NavState {
+    root: NavRoot,
+    subpath: PathBuf,
+}
+
+#[derive(Clone, PartialEq)]
+enum NavRoot {
+    Tab(String),
+    /// See `App::free_root`.
+    Dir(PathBuf),
+}
+
+/// One directory listing and where it is. There's one pane normally and
+/// two side by side in dual-pane mode, each navigated independently.
+struct Pane {
+    current_tab: usize,
+    /// Set when browsing outside the project tabs: the projects directory
+    /// itself for the overview, or any directory reached with `g`.
+    /// `current_subpath` is then relative to this instead of a tab.
+    free_root: Option<PathBuf>,
+    current_subpath: PathBuf,
+    history: History,
+    /// Everything in the current directory, as read from disk.
+    all_dir_contents: Vec<DirEntry>,
+    /// `all_dir_contents` after hidden-file, filter and sort settings; this
+    /// is what's listed and what `selected_item` indexes into.
     current_dir_contents: Vec<DirEntry>,
     selected_item: Option<usize>,
-    show_confirmation: bool,
+    /// First row the listing showed last frame, kept so it only scrolls
+    /// when the selection gets within `scroll_margin` of an edge.
+    scroll_offset: Cell<usize>,
+    /// Entries marked with Space or Shift+arrows, by full path so marks
+    /// survive moving around. C and M act on these when there are any.
+    marked: BTreeSet<PathBuf>,
+    /// Where a Shift+arrow range started, as an index into the listing.
+    anchor: Option<usize>,
+    /// Set while browsing inside an archive in the current directory.
+    archive: Option<ArchiveView>,
+}
+
+/// An archive opened with Enter and listed as if it were a directory. It's
+/// read in place, never extracted, and nothing in it can be changed.
+struct ArchiveView {
+    path: PathBuf,
+    format: ArchiveFormat,
+    members: Vec<archive::Member>,
+    /// The directory inside the archive being listed; empty at its top.
+    inner: PathBuf,
+}
+
+impl Pane {
+    fn new(current_tab: usize) -> Pane {
+        Pane {
+            current_tab,
+            free_root: None,
+            current_subpath: PathBuf::new(),
+            history: History::new(),
+            all_dir_contents: Vec::new(),
+            current_dir_contents: Vec::new(),
+            selected_item: None,
+            scroll_offset: Cell::new(0),
+            marked: BTreeSet::new(),
+            anchor: None,
+            archive: None,
+        }
+    }
+}
+
+/// Browser-style back/forward stack of visited directories.
+struct History {
+    entries: Vec<NavState>,
+    index: usize,
+}
+
+impl History {
+    fn new() -> History {
+        History { entries: Vec::new(), index: 0 }
+    }
+
+    /// Records a new navigation, dropping any forward history.
+    fn push(&mut self, state: NavState) {
+        if self.entries.get(self.index) == Some(&state) {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.index + 1);
+        }
+        self.entries.push(state);
+        self.index = self.entries.len() - 1;
+    }
+
+    fn back(&mut self) -> Option<NavState> {
+        if self.index == 0 || self.entries.is_empty() {
+            return None;
+        }
+        self.index -= 1;
+        self.entries.get(self.index).cloned()
+    }
+
+    fn forward(&mut self) -> Option<NavState> {
+        if self.index + 1 >= self.entries.len() {
+            return None;
+        }
+        self.index += 1;
+        self.entries.get(self.index).cloned()
+    }
+}
+
+/// Lists the project directories under `base_path` that should get a tab,
+/// honouring `hidden_tabs` and `tab_order`. `frecent` is the order for
+/// `tab_order = "frecency"`.
+///
+/// Tabs are known by name, so a directory whose name isn't valid UTF-8
+/// can't be one. Those are left out and returned second, lossily named,
+/// for the user to be told.
+fn discover_tabs(base_path: &Path, config: &Config, frecent: &[String]) -> io::Result<(Vec<String>, Vec<String>)> {
+    let mut tabs = Vec::new();
+    let mut skipped = Vec::new();
+    
+    for entry in fs::read_dir(base_path)?.flatten() {
+        if entry.file_type()?.is_dir() {
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(name) => {
+                    skipped.push(name.to_string_lossy().into_owned());
+                    continue;
+                },
+            };
+            if !config.hidden_tabs.contains(&name) {
+                tabs.push(name);
+            }
+        }
+    }
+    let mut custom: Vec<&String> = config.custom_tabs.keys()
+        .filter(|label| !config.hidden_tabs.contains(label) && !tabs.contains(label))
+        .collect();
+    custom.sort();
+    tabs.extend(custom.into_iter().cloned());
+    let order = match &config.tab_order {
+        TabOrder::Names(names) => names.as_slice(),
+        TabOrder::Frecency(_) => frecent,
+    };
+    // Stable sort: projects missing from the order keep discovery order.
+    tabs.sort_by_key(|t| order.iter().position(|o| o == t).unwrap_or(usize::MAX));
+    Ok((tabs, skipped))
+}
+
+/// Keys the main listing always handles itself, so `[[actions]]` can't
+/// use them. `f` is only taken while a formatter is configured.
+const BOUND_KEYS: &str = "q:PhsS/.poeduzRxvbOD[]U0H~gtYTNrc|CM,'AFXa ";
+
+/// Picks a distinct hotkey for each tab for the tab switcher, preferring
+/// the letters of its name in order and falling back to any free letter or
+/// digit. Tabs beyond the 36 available keys get `None`.
+fn tab_hotkeys(tabs: &[String]) -> Vec<Option<char>> {
+    let mut used = Vec::new();
+    tabs.iter()
+        .map(|tab| {
+            let key = tab.chars()
+                .flat_map(char::to_lowercase)
+                .filter(char::is_ascii_alphanumeric)
+                .chain(('a'..='z').chain('0'..='9'))
+                .find(|c| !used.contains(c));
+            used.extend(key);
+            key
+        })
+        .collect()
 }
 
-#[derive(Clone)]
-struct DirEntry {
-    name: String,
-    is_dir: bool,
-}
+impl App {
+    fn new(config: Config) -> io::Result<App> {
+        let catalog_error = i18n::load(config.locale.as_deref()).err();
+        let projects_dir = config.projects_dir.as_deref().unwrap_or(config::DEFAULT_PROJECTS_DIR);
+        let base_path = Path::new(projects_dir).expand_home()?;
+        if !base_path.is_dir() {
+            let problem = if base_path.exists() { "projects_not_dir" } else { "no_projects_dir" };
+            return Err(io::Error::other(i18n::trf(problem, &[("path", &base_path.display())])));
+        }
+        let visits = Visits::load();
+        // Ranked once, so tabs don't move around while they're being used.
+        let frecent_tabs = visits.ranked();
+        let (tabs, skipped_tabs) = discover_tabs(&base_path, &config, &frecent_tabs)?;
+        let session = Session::load();
+        
+        let mut app = App {
+            theme: Theme::for_terminal(config.theme),
+            clock: Clock::new(&config),
+            show_content: config.start_view == StartView::Content,
+            show_editor_selection: config.start_popup == StartPopup::EditorSelection,
+            show_welcome: config.start_popup == StartPopup::Welcome,
+            graphics: config.image_previews && graphics::supported(),
+            side_tabs: config.tab_position == TabPosition::Side,
+            search_case_sensitive: config.search_case_sensitive,
+            config,
+            base_path,
+            tabs,
+            manifests: HashMap::new(),
+            tab_subpaths: HashMap::new(),
+            pane: Pane::new(0),
+            other_pane: None,
+            pane_on_left: true,
+            quit: false,
+            selected_editor: 0,
+            confirmation: None,
+            pending_transfer: None,
+            confirmation_asked: Instant::now(),
+            recent_launches: Vec::new(),
+            input: None,
+            command_output: None,
+            startup_command: None,
+            action_command: None,
+            refresh_after_output: false,
+            disk_usage: DiskUsage::new(),
+            archive_job: None,
+            last_refresh: Instant::now(),
+            free_space: None,
+            free_space_checked: None,
+            saved_session: session.clone(),
+            visits,
+            visits_changed: false,
+            frecent_tabs,
+            last_session_check: Instant::now(),
+            last_input: Instant::now(),
+            path_display: session.path_display,
+            status_message: None,
+            hidden_tabs_popup: None,
+            tab_switcher: None,
+            tab_search: None,
+            recent: None,
+            finder: None,
+            finder_files: HashMap::new(),
+            note_popup: None,
+            config_reload_pending: false,
+            config_watcher: ConfigWatcher::start(),
+            show_hidden: session.show_hidden,
+            show_preview: session.show_preview,
+            preview: None,
+            preview_scroll: 0,
+            preview_focused: false,
+            sidebar_focused: false,
+            image_area: Cell::new(None),
+            drawn_image: None,
+            outline_mode: session.outline_mode,
+            diff_mode: session.diff_mode,
+            outline_selected: 0,
+            sort_mode: session.sort_mode,
+            sort_reverse: session.sort_reverse,
+            filter: None,
+            color_rules: ColorRules::default(),
+            undo_stack: Vec::new(),
+        };
+        
+        // Initialize directory contents
+        app.load_manifests();
+        app.update_current_dir_contents()?;
+        app.record_navigation();
+        if session.dual_pane {
+            app.toggle_split()?;
+        }
+        app.report_skipped_tabs(&skipped_tabs);
+        if let Some(key) = app.shadowed_action_key() {
+            app.status_message = Some(i18n::trf("action_key_taken", &[("key", &key)]));
+        }
+        if let Some(e) = catalog_error {
+            app.status_message = Some(i18n::trf("catalog_not_loaded", &[("error", &e)]));
+        }
+        if let Err(e) = app.load_color_rules() {
+            app.status_message = Some(e.to_string());
+        }
+        
+        Ok(app)
+    }
+
+    /// The view settings to restore next time.
+    fn session(&self) -> Session {
+        Session {
+            show_hidden: self.show_hidden,
+            show_preview: self.show_preview,
+            outline_mode: self.outline_mode,
+            diff_mode: self.diff_mode,
+            sort_mode: self.sort_mode,
+            sort_reverse: self.sort_reverse,
+            path_display: self.path_display,
+            dual_pane: self.other_pane.is_some(),
+        }
+    }
+
+    /// Name of the project being browsed; `None` under a free root or when
+    /// there are no tabs.
+    fn current_tab_name(&self) -> Option<&String> {
+        self.tab_name_of(&self.pane)
+    }
+
+    fn tab_name_of(&self, pane: &Pane) -> Option<&String> {
+        if pane.free_root.is_some() {
+            return None;
+        }
+        self.tabs.get(pane.current_tab)
+    }
+
+    /// The directory tab `tab` shows: its project directory, or wherever a
+    /// custom tab points.
+    fn tab_root(&self, tab: &str) -> PathBuf {
+        match self.config.custom_tabs.get(tab) {
+            Some(path) => Path::new(path).expand_home().unwrap_or_else(|_| PathBuf::from(path)),
+            None => self.base_path.join(tab),
+        }
+    }
+
+    /// True at the top of the All Projects overview, where entering a
+    /// directory switches to that project.
+    fn in_overview(&self) -> bool {
+        self.in_overview_of(&self.pane)
+    }
+
+    fn in_overview_of(&self, pane: &Pane) -> bool {
+        pane.free_root.as_ref() == Some(&self.base_path) && pane.current_subpath.as_os_str().is_empty()
+    }
+
+    /// Keys that jump straight to a tab, the first to tab one and so on.
+    fn tab_keys(&self) -> &str {
+        self.config.tab_keys.as_deref().unwrap_or(config::DEFAULT_TAB_KEYS)
+    }
+
+    /// The key that jumps to tab `index` on its own, if there is one.
+    fn tab_key(&self, index: usize) -> Option<char> {
+        self.tab_keys().chars().nth(index)
+    }
+
+    /// Absolute path of the directory currently being listed.
+    fn current_dir(&self) -> PathBuf {
+        self.dir_of(&self.pane)
+    }
+
+    fn dir_of(&self, pane: &Pane) -> PathBuf {
+        if let Some(root) = &pane.free_root {
+            return root.join(&pane.current_subpath);
+        }
+        match self.tab_name_of(pane) {
+            Some(tab) => self.tab_root(tab).join(&pane.current_subpath),
+            None => self.base_path.clone(),
+        }
+    }
+
+    /// The current location formatted according to `path_display`.
+    fn display_path(&self) -> String {
+        self.display_path_of(&self.pane)
+    }
+
+    fn display_path_of(&self, pane: &Pane) -> String {
+        let path = match self.path_display {
+            PathDisplay::Base | PathDisplay::Tab if self.in_overview_of(pane) => i18n::tr("all_projects"),
+            _ if pane.free_root.is_some() => self.dir_of(pane).display().to_string(),
+            PathDisplay::Base => match self.tab_name_of(pane) {
+                Some(tab) => Path::new(tab).join(&pane.current_subpath).display().to_string(),
+                None => String::new(),
+            },
+            PathDisplay::Tab => Path::new("/").join(&pane.current_subpath).display().to_string(),
+            PathDisplay::Absolute => self.dir_of(pane).display().to_string(),
+        };
+        match &pane.archive {
+            Some(view) => Path::new(&path)
+                .join(view.path.file_name().unwrap_or_default())
+                .join(&view.inner)
+                .display()
+                .to_string(),
+            None => path,
+        }
+    }
+
+    fn update_current_dir_contents(&mut self) -> io::Result<()> {
+        if self.current_tab_name().is_none() && self.pane.free_root.is_none() {
+            self.pane.all_dir_contents.clear();
+            self.pane.current_dir_contents.clear();
+            return Ok(());
+        }
+
+        let current_dir = self.current_dir();
+        // Moved somewhere else in a way that didn't close the archive.
+        if self.pane.archive.as_ref().is_some_and(|view| view.path.parent() != Some(current_dir.as_path())) {
+            self.pane.archive = None;
+        }
+        if let Some(view) = &self.pane.archive {
+            self.pane.all_dir_contents = view.members
+                .iter()
+                .filter(|m| m.path.parent() == Some(view.inner.as_path()))
+                .map(|member| {
+                    let name = member.path.file_name().unwrap_or_default().to_os_string();
+                    let mut entry = DirEntry::new(name, member.is_dir);
+                    entry.size = member.size;
+                    entry.modified = member.modified;
+                    if member.is_dir {
+                        entry.child_count = Some(view.members.iter().filter(|m| m.path.parent() == Some(member.path.as_path())).count());
+                    }
+                    entry
+                })
+                .collect();
+            self.apply_view();
+            return Ok(());
+        }
+
+        let mut contents = Vec::new();
+
+        for entry in fs::read_dir(current_dir)?.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let mut dir_entry = DirEntry::new(entry.file_name(), file_type.is_dir());
+            // `metadata` follows the link, so it fails when there's nothing
+            // at the other end.
+            dir_entry.broken_link = file_type.is_symlink() && fs::metadata(entry.path()).is_err();
+            if let Ok(metadata) = entry.metadata() {
+                dir_entry.size = metadata.len();
+                dir_entry.modified = metadata.modified().ok();
+            }
+            if dir_entry.is_dir {
+                dir_entry.child_count = fs::read_dir(entry.path()).map(|d| d.count()).ok();
+            }
+            dir_entry.pinned = self.is_pinned(&dir_entry.file_name);
+            contents.push(dir_entry);
+        }
+
+        self.pane.all_dir_contents = contents;
+        self.apply_view();
+        Ok(())
+    }
+
+    /// Rebuilds `current_dir_contents` from `all_dir_contents` using the
+    /// hidden-file, filter and sort settings, without touching the disk.
+    /// The selected entry stays selected by name where it's still listed.
+    fn apply_view(&mut self) {
+        let selected = self.selected_name();
+
+        let mut contents: Vec<DirEntry> = self.pane.all_dir_contents
+            .iter()
+            .filter(|e| self.show_hidden || !e.name.starts_with('.'))
+            .filter(|e| match &self.filter {
+                Some(filter) => fuzzy::score(filter, &e.name, self.search_case_sensitive).is_some(),
+                None => true,
+            })
+            .cloned()
+            .collect();
+
+        contents.sort_by(|a, b| {
+            match (a.pinned, b.pinned, a.is_dir, b.is_dir) {
+                (true, false, _, _) => std::cmp::Ordering::Less,
+                (false, true, _, _) => std::cmp::Ordering::Greater,
+                (_, _, true, false) => std::cmp::Ordering::Less,
+                (_, _, false, true) => std::cmp::Ordering::Greater,
+                _ => self.sort_first_rank(&a.name).cmp(&self.sort_first_rank(&b.name)).then_with(|| {
+                    if self.sort_reverse { self.sort_mode.compare(b, a) } else { self.sort_mode.compare(a, b) }
+                }),
+            }
+        });
+
+        // Ahead of everything, pinned entries included, and exempt from the
+        // filter so the way up is always there.
+        if self.config.parent_entry && self.can_go_up() {
+            contents.insert(0, DirEntry::parent());
+        }
+        self.pane.current_dir_contents = contents;
+        match selected {
+            Some(name) => self.select_name(&name, self.pane.selected_item),
+            None => self.pane.selected_item = None,
+        }
+    }
+
+    /// Where `name` comes in `sort_first`: the first entry that's the
+    /// whole name or, starting with a dot, its extension. Past the end of
+    /// the list if none is.
+    fn sort_first_rank(&self, name: &str) -> usize {
+        let extension = Path::new(name).extension().map(|e| e.to_string_lossy().to_lowercase());
+        self.config.sort_first
+            .iter()
+            .position(|first| match first.strip_prefix('.') {
+                Some(wanted) => extension.as_deref() == Some(wanted.to_lowercase().as_str()),
+                None => first == name,
+            })
+            .unwrap_or(usize::MAX)
+    }
+
+    /// True if Backspace would go anywhere from here.
+    fn can_go_up(&self) -> bool {
+        if self.pane.archive.is_some() || !self.pane.current_subpath.as_os_str().is_empty() {
+            return true;
+        }
+        !self.in_overview() && self.pane.free_root.as_ref().is_some_and(|root| root.parent().is_some())
+    }
+
+    /// The selected entry, unless that's the `..` row.
+    fn selected_entry(&self) -> Option<&DirEntry> {
+        self.pane.selected_item
+            .and_then(|i| self.pane.current_dir_contents.get(i))
+            .filter(|e| !e.parent)
+    }
+
+    /// On-disk name of the selected entry. Selection is remembered by name
+    /// across anything that rebuilds the listing, since indexes shift.
+    fn selected_name(&self) -> Option<OsString> {
+        self.pane.selected_item
+            .and_then(|i| self.pane.current_dir_contents.get(i))
+            .map(|e| e.file_name.clone())
+    }
+
+    /// Selects the entry called `name`. If it's no longer listed, selects
+    /// whatever is now at `fallback`, clamped to the end of the list.
+    fn select_name(&mut self, name: &OsStr, fallback: Option<usize>) {
+        let last = self.pane.current_dir_contents.len().checked_sub(1);
+        self.pane.selected_item = self.pane.current_dir_contents
+            .iter()
+            .position(|e| e.file_name == name)
+            .or_else(|| Some(fallback?.min(last?)));
+    }
+
+    /// Path of `file_name` in the current directory relative to the tab
+    /// root, as stored in `Config::pinned`. `None` if it isn't valid UTF-8.
+    fn pin_key(&self, file_name: &OsStr) -> Option<String> {
+        self.pane.current_subpath.join(file_name).to_str().map(str::to_owned)
+    }
+
+    fn is_pinned(&self, file_name: &OsStr) -> bool {
+        let (Some(tab), Some(key)) = (self.current_tab_name(), self.pin_key(file_name)) else {
+            return false;
+        };
+        self.config.pinned.get(tab).is_some_and(|pins| pins.contains(&key))
+    }
+
+    /// Pins or unpins the selected entry and saves the change.
+    fn toggle_pin(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let Some(tab) = self.current_tab_name().cloned() else {
+            return;
+        };
+        let Some(key) = self.pin_key(&entry.file_name) else {
+            self.status_message = Some(i18n::trf("cant_pin", &[("name", &entry.name)]));
+            return;
+        };
+        let file_name = entry.file_name.clone();
+        let pins = self.config.pinned.entry(tab.clone()).or_default();
+        let pinned = match pins.iter().position(|p| *p == key) {
+            Some(index) => {
+                pins.remove(index);
+                false
+            },
+            None => {
+                pins.push(key);
+                true
+            },
+        };
+        if pins.is_empty() {
+            self.config.pinned.remove(&tab);
+        }
+        for entry in self.pane.all_dir_contents.iter_mut().filter(|e| e.file_name == file_name) {
+            entry.pinned = pinned;
+        }
+        self.apply_view();
+        if let Err(e) = config::set_key("pinned", config::string_list_table(&self.config.pinned)) {
+            self.status_message = Some(i18n::trf("cant_save_pins", &[("error", &e)]));
+        }
+    }
+
+    /// While a confirmation is up it takes every key, so the entry it
+    /// names can't change under it.
+    fn handle_confirmation_key(&mut self, key: KeyEvent, terminal: &mut launch::Tui) -> io::Result<()> {
+        self.status_message = None;
+        let overwrite = matches!(self.confirmation, Some(Confirmation::Overwrite(_)));
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.dismiss_confirmation(),
+            KeyCode::Char('y') if overwrite => self.resolve_conflicts(Some(Conflict::Overwrite))?,
+            KeyCode::Char('n') if overwrite => self.resolve_conflicts(None)?,
+            KeyCode::Char('r') if overwrite => self.resolve_conflicts(Some(Conflict::Rename))?,
+            KeyCode::Char('y') => self.accept_confirmation(terminal),
+            KeyCode::Char('n') => self.dismiss_confirmation(),
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// Acts on a `y` to the open confirmation. When the file was opened
+    /// within `reopen_confirm_secs`, asks again before launching.
+    fn accept_confirmation(&mut self, terminal: &mut launch::Tui) {
+        let (name, path) = match self.confirmation.take() {
+            Some(Confirmation::Extract(name, path)) => {
+                self.extract(&path, &name);
+                return;
+            },
+            Some(Confirmation::Open(name, path)) if self.recently_launched(&path) => {
+                self.ask(Confirmation::Reopen(name, path));
+                return;
+            },
+            Some(Confirmation::Open(name, path) | Confirmation::Reopen(name, path)) => (name, path),
+            Some(Confirmation::Overwrite(_)) | None => return,
+        };
+        match launch::open_file(terminal, &self.config, self.current_tab_name().map(String::as_str), &path, self.outline_line(&path)) {
+            Ok(handler) => {
+                let handler = match handler {
+                    Handler::Program(program) => program,
+                    Handler::DefaultApp => i18n::tr("default_app"),
+                };
+                self.status_message = Some(i18n::trf("opened_with", &[("name", &name), ("handler", &handler)]));
+                self.quit = self.config.quit_after_open;
+                if self.config.reopen_confirm_secs.is_some() {
+                    self.recent_launches.push((path, Instant::now()));
+                }
+            },
+            Err(e) => self.status_message = Some(i18n::trf("cant_open", &[("name", &name), ("error", &e)])),
+        }
+    }
+
+    /// Line of the symbol picked in the outline, if `path` is being shown as
+    /// one, so the editor can open there.
+    fn outline_line(&self, path: &Path) -> Option<usize> {
+        if !self.outline_mode {
+            return None;
+        }
+        let preview = self.preview.as_ref().filter(|p| p.path == path)?;
+        preview.outline.as_ref()?.get(self.outline_selected).map(|s| s.line)
+    }
+
+    /// Loads the preview of the selected file unless it's already showing.
+    fn update_preview(&mut self) {
+        let selected = self.selected_entry();
+        let (path, member) = match (selected, &self.pane.archive) {
+            (Some(entry), Some(view)) if self.show_preview => {
+                let member = view.inner.join(&entry.file_name);
+                (view.path.join(&member), Some((member, entry.is_dir)))
+            },
+            (Some(entry), None) if self.show_preview => (self.current_dir().join(&entry.file_name), None),
+            _ => {
+                self.preview = None;
+                return;
+            },
+        };
+        // git has nothing to say about what's inside an archive.
+        let diff = self.diff_mode && member.is_none();
+        if self.preview.as_ref().is_none_or(|p| p.path != path || p.diff != diff) {
+            self.preview = Some(match (&self.pane.archive, member) {
+                // The archive was listed when it was opened; no need to read it again.
+                (Some(view), Some((member, true))) => {
+                    let children = view.members
+                        .iter()
+                        .filter(|m| m.path.parent() == Some(member.as_path()))
+                        .map(|m| DirChild {
+                            name: m.path.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                            is_dir: m.is_dir,
+                        })
+                        .collect();
+                    Preview::listing(&path, children)
+                },
+                (Some(view), Some((member, false))) => Preview::load_member(&view.path, view.format, &member),
+                _ => Preview::load(&path, diff),
+            });
+            self.preview_scroll = 0;
+            self.outline_selected = 0;
+        }
+        let Some(preview) = self.preview.as_mut() else {
+            return;
+        };
+        preview.poll();
+        // Keep a screen ahead of what's shown; the outline needs it all.
+        // Before the first frame there's no height yet, so guess.
+        let height = match preview.view_height.get() {
+            0 => 50,
+            height => height as usize,
+        };
+        preview.want_lines(if self.outline_mode { usize::MAX } else { self.preview_scroll + 2 * height });
+    }
+
+    /// True while anything is drawn over the main view. Images sit on top
+    /// of ratatui's output, so they're taken down until it's gone.
+    fn overlay_open(&self) -> bool {
+        self.show_welcome
+            || self.show_editor_selection
+            || self.tab_switcher.is_some()
+            || self.confirmation.is_some()
+            || self.command_output.is_some()
+            || self.note_popup.is_some()
+            || self.tab_search.is_some()
+            || self.recent.is_some()
+            || self.finder.is_some()
+            || self.hidden_tabs_popup.is_some()
+            || self.input.as_ref().is_some_and(|i| i.completion.is_some())
+    }
+
+    /// Draws, moves or removes the preview image to match the frame just
+    /// drawn. Removing one repaints the whole screen on the next frame,
+    /// which then draws any new image.
+    fn sync_image(&mut self, terminal: &mut launch::Tui) -> io::Result<()> {
+        let target = match (&self.preview, self.image_area.get()) {
+            (Some(preview), Some(area)) if self.graphics && !self.overlay_open() && !self.is_idle() => {
+                Some((preview.path.clone(), area))
+            },
+            _ => None,
+        };
+        if target == self.drawn_image {
+            return Ok(());
+        }
+        if self.drawn_image.take().is_some() {
+            graphics::clear()?;
+            terminal.clear()?;
+        } else if let Some((path, area)) = target {
+            if let Err(e) = graphics::draw(&path, area) {
+                self.status_message = Some(i18n::trf("cant_draw_image", &[("error", &e)]));
+            }
+            self.drawn_image = Some((path, area));
+        }
+        Ok(())
+    }
+
+    /// Scrolls the preview by a line or a page. Past the end of what's been
+    /// read so far is fine while more is loading.
+    fn scroll_preview(&mut self, down: bool, by_page: bool) {
+        let Some(preview) = &self.preview else {
+            return;
+        };
+        let len = match &preview.body {
+            PreviewBody::Text(lines) | PreviewBody::Diff(lines) => lines.len(),
+            PreviewBody::Dir(children) => self.shown_children(children).count(),
+            _ => return,
+        };
+        let page = (preview.view_height.get() as usize).max(1);
+        let amount = if by_page { page } else { 1 };
+        self.preview_scroll = if down {
+            let last = if preview.loading { len } else { len.saturating_sub(page) };
+            (self.preview_scroll + amount).min(last.max(self.preview_scroll))
+        } else {
+            self.preview_scroll.saturating_sub(amount)
+        };
+    }
+
+    /// The entries of a directory preview that are drawn, leaving out hidden
+    /// ones unless they're shown.
+    fn shown_children<'a>(&self, children: &'a [DirChild]) -> impl Iterator<Item = &'a DirChild> {
+        let show_hidden = self.show_hidden;
+        children.iter().filter(move |child| show_hidden || !child.name.starts_with('.'))
+    }
+
+    /// Moves focus on to the next of: the side tab list when it's shown,
+    /// the left pane, the right pane in dual-pane mode, and the preview
+    /// when it's shown.
+    fn cycle_focus(&mut self) -> io::Result<()> {
+        let split = self.other_pane.is_some();
+        if self.sidebar_focused {
+            self.sidebar_focused = false;
+        } else if split && self.pane_on_left && !self.preview_focused {
+            self.switch_pane()?;
+        } else if self.show_preview && !self.preview_focused {
+            self.preview_focused = true;
+        } else {
+            // Back round to the side tabs if shown, else the left pane.
+            self.preview_focused = false;
+            self.sidebar_focused = self.side_tabs;
+            if split && !self.pane_on_left {
+                self.switch_pane()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn toggle_side_tabs(&mut self) {
+        self.side_tabs = !self.side_tabs;
+        self.sidebar_focused = false;
+    }
+
+    fn move_outline_selection(&mut self, forward: bool) {
+        let count = self.preview.as_ref().and_then(|p| p.outline.as_ref()).map_or(0, Vec::len);
+        self.outline_selected = if forward {
+            (self.outline_selected + 1).min(count.saturating_sub(1))
+        } else {
+            self.outline_selected.saturating_sub(1)
+        };
+    }
+
+    fn ask(&mut self, confirmation: Confirmation) {
+        self.confirmation = Some(confirmation);
+        self.confirmation_asked = Instant::now();
+    }
+
+    /// Takes down the confirmation unanswered, along with any transfer
+    /// that was waiting on it.
+    fn dismiss_confirmation(&mut self) {
+        self.confirmation = None;
+        self.pending_transfer = None;
+    }
+
+    /// Takes down a confirmation left unanswered for `confirm_timeout_secs`,
+    /// as if answered no.
+    fn expire_confirmation(&mut self) {
+        let Some(secs) = self.config.confirm_timeout_secs else {
+            return;
+        };
+        if self.confirmation.is_some() && self.confirmation_asked.elapsed() >= Duration::from_secs(secs) {
+            self.dismiss_confirmation();
+            self.status_message = Some(i18n::tr("confirmation_timed_out"));
+        }
+    }
+
+    /// True if `path` was opened in the editor within `reopen_confirm_secs`.
+    fn recently_launched(&mut self, path: &Path) -> bool {
+        let Some(secs) = self.config.reopen_confirm_secs else {
+            return false;
+        };
+        let window = Duration::from_secs(secs);
+        self.recent_launches.retain(|(_, at)| at.elapsed() < window);
+        self.recent_launches.iter().any(|(p, _)| p == path)
+    }
+
+    /// Runs the current project's task: the command configured under
+    /// `[tasks]`, or one guessed from its manifest. Output streams into the
+    /// command output view.
+    fn run_task(&mut self) {
+        let Some(tab) = self.current_tab_name() else {
+            return;
+        };
+        let root = self.tab_root(tab);
+        let command = self.config.tasks
+            .get(tab)
+            .cloned()
+            .or_else(|| manifest::default_task(&root).map(str::to_owned));
+        match command {
+            Some(command) => self.command_output = Some(CommandOutput::spawn(&command, &root)),
+            None => self.status_message = Some(i18n::trf("no_task", &[("name", tab)])),
+        }
+    }
+
+    /// Starts archiving the selected entry next to itself in the configured
+    /// format. Only one archive job runs at a time.
+    fn compress_selected(&mut self) {
+        if self.archive_job.is_some() {
+            self.status_message = Some(i18n::tr("archive_running"));
+            return;
+        }
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let source = self.current_dir().join(&entry.file_name);
+        self.archive_job = Some(ArchiveJob::compress(&source, &entry.name, self.config.archive_format));
+    }
+
+    /// Turns dual-pane mode on or off. The second pane starts out where
+    /// the focused one is; turning it off keeps whichever pane has focus.
+    fn toggle_split(&mut self) -> io::Result<()> {
+        if self.other_pane.take().is_some() {
+            self.pane_on_left = true;
+            return Ok(());
+        }
+        let mut other = Pane::new(self.pane.current_tab);
+        other.free_root = self.pane.free_root.clone();
+        other.current_subpath = self.pane.current_subpath.clone();
+        self.other_pane = Some(other);
+        self.with_other_pane(App::refresh);
+        Ok(())
+    }
+
+    /// Moves focus to the other pane, re-reading it in case it's stale.
+    fn switch_pane(&mut self) -> io::Result<()> {
+        let Some(other) = self.other_pane.as_mut() else {
+            return Ok(());
+        };
+        std::mem::swap(&mut self.pane, other);
+        self.pane_on_left = !self.pane_on_left;
+        self.refresh();
+        Ok(())
+    }
+
+    /// Runs `f` with the unfocused pane swapped in as `pane`.
+    fn with_other_pane<T>(&mut self, f: impl FnOnce(&mut App) -> T) -> T {
+        let mut other = self.other_pane.take().expect("dual-pane mode is on");
+        std::mem::swap(&mut self.pane, &mut other);
+        let result = f(self);
+        std::mem::swap(&mut self.pane, &mut other);
+        self.other_pane = Some(other);
+        result
+    }
+
+    /// Copies, or moves, the selected entry into the other pane's directory.
+    fn transfer_selected(&mut self, remove_source: bool) -> io::Result<()> {
+        let Some(other) = &self.other_pane else {
+            self.status_message = Some(i18n::tr("no_second_pane"));
+            return Ok(());
+        };
+        if self.in_overview_of(other) || self.tab_name_of(other).is_none() && other.free_root.is_none() {
+            self.status_message = Some(i18n::tr("other_pane_not_dir"));
+            return Ok(());
+        }
+        if other.archive.is_some() {
+            self.status_message = Some(i18n::tr("archive_read_only"));
+            return Ok(());
+        }
+        let sources = self.marked_or_selected();
+        let dest_dir = self.dir_of(other);
+        if !self.transfer(&sources, &dest_dir, remove_source)? {
+            return Ok(());
+        }
+        let last_name = sources.last().and_then(|source| source.file_name()).map(OsStr::to_os_string);
+        self.with_other_pane(|app| {
+            app.refresh();
+            if let Some(file_name) = last_name {
+                app.select_name(&file_name, app.pane.selected_item);
+            }
+            Ok(())
+        })
+    }
+
+    /// Copies the marked entries, or the selected one, into the root of
+    /// tab `index`, leaving the view where it is.
+    fn copy_to_tab(&mut self, index: usize) -> io::Result<()> {
+        let Some(tab) = self.tabs.get(index) else {
+            return Ok(());
+        };
+        let dest_dir = self.tab_root(tab);
+        let sources = self.marked_or_selected();
+        if self.transfer(&sources, &dest_dir, false)? && self.other_pane.is_some() {
+            self.with_other_pane(App::refresh);
+        }
+        Ok(())
+    }
+
+    /// Copies or moves `sources` into `dest_dir`, doing what `on_conflict`
+    /// says about entries already there. When that's to ask, nothing
+    /// happens until the question is answered. False if nothing was done.
+    fn transfer(&mut self, sources: &[PathBuf], dest_dir: &Path, remove_source: bool) -> io::Result<bool> {
+        let conflicts: Vec<&PathBuf> = sources.iter().filter(|source| fileops::collides(source, dest_dir)).collect();
+        let Some(first) = conflicts.first() else {
+            return self.transfer_with(sources, dest_dir, remove_source, Conflict::Refuse);
+        };
+        match self.config.on_conflict {
+            OnConflict::Ask => {
+                let name = first.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                let name = match conflicts.len() {
+                    1 => name,
+                    count => i18n::trf("name_and_more", &[("name", &name), ("count", &(count - 1))]),
+                };
+                self.pending_transfer = Some(PendingTransfer {
+                    sources: sources.to_vec(),
+                    dest_dir: dest_dir.to_path_buf(),
+                    remove_source,
+                });
+                self.ask(Confirmation::Overwrite(name));
+                Ok(false)
+            },
+            OnConflict::Skip => self.skip_conflicts(sources, dest_dir, remove_source),
+            OnConflict::Overwrite => self.transfer_with(sources, dest_dir, remove_source, Conflict::Overwrite),
+            OnConflict::Rename => self.transfer_with(sources, dest_dir, remove_source, Conflict::Rename),
+        }
+    }
+
+    /// Carries out `pending_transfer` once asked about conflicts:
+    /// `conflict` says what to do with them, or `None` to skip them.
+    fn resolve_conflicts(&mut self, conflict: Option<Conflict>) -> io::Result<()> {
+        self.confirmation = None;
+        let Some(PendingTransfer { sources, dest_dir, remove_source }) = self.pending_transfer.take() else {
+            return Ok(());
+        };
+        let done = match conflict {
+            Some(conflict) => self.transfer_with(&sources, &dest_dir, remove_source, conflict)?,
+            None => self.skip_conflicts(&sources, &dest_dir, remove_source)?,
+        };
+        if done && self.other_pane.is_some() {
+            self.with_other_pane(App::refresh);
+        }
+        Ok(())
+    }
+
+    /// Transfers only the `sources` with nothing in the way in `dest_dir`.
+    fn skip_conflicts(&mut self, sources: &[PathBuf], dest_dir: &Path, remove_source: bool) -> io::Result<bool> {
+        let clear: Vec<PathBuf> = sources.iter().filter(|source| !fileops::collides(source, dest_dir)).cloned().collect();
+        if clear.is_empty() {
+            self.status_message = Some(i18n::trf("skipped_all", &[("count", &sources.len())]));
+        }
+        self.transfer_with(&clear, dest_dir, remove_source, Conflict::Refuse)
+    }
+
+    /// Copies or moves `sources` into `dest_dir`, stopping at the first
+    /// failure, and reports how it went. What was done is recorded for
+    /// undo and this pane refreshed. False if nothing was done.
+    fn transfer_with(&mut self, sources: &[PathBuf], dest_dir: &Path, remove_source: bool, conflict: Conflict) -> io::Result<bool> {
+        if sources.is_empty() {
+            return Ok(false);
+        }
+        let mut operations = Vec::new();
+        let mut failure = None;
+        for source in sources {
+            let result = if remove_source {
+                fileops::move_to(source, dest_dir, conflict)
+            } else {
+                fileops::copy(source, dest_dir, conflict)
+            };
+            match result {
+                Ok(operation) => operations.push(operation),
+                // Stop at the first failure; what's done so far can be undone.
+                Err(e) => {
+                    failure = Some((source, e));
+                    break;
+                },
+            }
+        }
+        let name = |path: &Path| path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let (done, done_many, failed) = if remove_source {
+            ("moved", "moved_many", "cant_move")
+        } else {
+            ("copied", "copied_many", "cant_copy")
+        };
+        self.status_message = match (&failure, operations.as_slice()) {
+            (Some((source, e)), _) => Some(i18n::trf(failed, &[("name", &name(source)), ("error", e)])),
+            (None, [Operation::Moved { to: target, .. }] | [Operation::Copied { copy: target, .. }]) => {
+                Some(i18n::trf(done, &[("name", &name(&sources[0])), ("path", &target.display())]))
+            },
+            (None, _) => Some(i18n::trf(done_many, &[("count", &operations.len()), ("path", &dest_dir.display())])),
+        };
+        if operations.is_empty() {
+            return Ok(false);
+        }
+        self.record_operations(operations);
+        if failure.is_none() {
+            self.pane.marked.clear();
+        }
+        self.refresh();
+        Ok(true)
+    }
+
+    /// The marked entries, or else the selected one.
+    fn marked_or_selected(&self) -> Vec<PathBuf> {
+        if !self.pane.marked.is_empty() {
+            return self.pane.marked.iter().cloned().collect();
+        }
+        self.selected_entry()
+            .map(|entry| self.current_dir().join(&entry.file_name))
+            .into_iter()
+            .collect()
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(selected) = self.pane.selected_item else {
+            return;
+        };
+        let Some(entry) = self.pane.current_dir_contents.get(selected).filter(|e| !e.parent) else {
+            return;
+        };
+        let path = self.current_dir().join(&entry.file_name);
+        if !self.pane.marked.remove(&path) {
+            self.pane.marked.insert(path);
+        }
+        self.pane.anchor = Some(selected);
+    }
+
+    /// Moves the cursor with Shift held, marking the range from the anchor
+    /// to the cursor. Moving back towards the anchor unmarks again.
+    fn extend_marks(&mut self, down: bool) {
+        let len = self.pane.current_dir_contents.len();
+        if len == 0 {
+            return;
+        }
+        let cursor = self.pane.selected_item.unwrap_or(0).min(len - 1);
+        let anchor = *self.pane.anchor.get_or_insert(cursor);
+        let next = if down { (cursor + 1).min(len - 1) } else { cursor.saturating_sub(1) };
+        let dir = self.current_dir();
+        // The `..` row is passed over, never marked.
+        let path_at = |i: usize| {
+            let entry = &self.pane.current_dir_contents[i];
+            (!entry.parent).then(|| dir.join(&entry.file_name))
+        };
+        let (cursor_path, next_path) = (path_at(cursor), path_at(next));
+        if next.abs_diff(anchor) >= cursor.abs_diff(anchor) {
+            self.pane.marked.extend(cursor_path);
+            self.pane.marked.extend(next_path);
+        } else if let Some(cursor_path) = cursor_path {
+            self.pane.marked.remove(&cursor_path);
+        }
+        self.pane.selected_item = Some(next);
+    }
+
+    fn record_operations(&mut self, operations: Vec<Operation>) {
+        if self.undo_stack.len() == UNDO_DEPTH {
+            self.undo_stack.remove(0).iter().for_each(Operation::forget);
+        }
+        self.undo_stack.push(operations);
+    }
+
+    /// Reverses the most recent copy or move that hasn't been undone yet,
+    /// all of it when it covered several entries.
+    fn undo(&mut self) -> io::Result<()> {
+        let Some(operations) = self.undo_stack.pop() else {
+            self.status_message = Some(i18n::tr("nothing_to_undo"));
+            return Ok(());
+        };
+        // Last first, in case later ones depend on earlier ones.
+        let mut result = Ok(PathBuf::new());
+        for operation in operations.iter().rev() {
+            result = operation.undo();
+            if result.is_err() {
+                break;
+            }
+        }
+        self.status_message = Some(match (result, operations.as_slice()) {
+            (Err(e), _) => i18n::trf("cant_undo", &[("error", &e)]),
+            (Ok(_), [Operation::Copied { copy, replaced: Some(_) }]) => i18n::trf("undid_overwrite", &[("path", &copy.display())]),
+            (Ok(_), [Operation::Copied { copy, .. }]) => i18n::trf("undid_copy", &[("path", &copy.display())]),
+            (Ok(path), [Operation::Moved { .. }]) => i18n::trf("undid_move", &[("path", &path.display())]),
+            (Ok(_), _) => i18n::trf("undid_many", &[("count", &operations.len())]),
+        });
+        self.refresh_panes();
+        Ok(())
+    }
+
+    /// Asks to extract the selected entry if it's an archive bod can read.
+    fn confirm_extract(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        if entry.is_dir || ArchiveFormat::detect(&entry.name).is_none() {
+            self.status_message = Some(i18n::trf("not_archive", &[("name", &entry.name)]));
+            return;
+        }
+        let archive = self.current_dir().join(&entry.file_name);
+        self.ask(Confirmation::Extract(entry.name.clone(), archive));
+    }
+
+    /// Starts extracting `archive`, shown as `name`, into a sibling
+    /// directory.
+    fn extract(&mut self, archive: &Path, name: &str) {
+        if self.archive_job.is_some() {
+            self.status_message = Some(i18n::tr("archive_running"));
+            return;
+        }
+        let Some((format, _)) = ArchiveFormat::detect(name) else {
+            return;
+        };
+        self.archive_job = Some(ArchiveJob::extract(archive, name, format));
+    }
+
+    /// Picks up archive progress, and when the job ends reports it and
+    /// refreshes so the new archive is listed.
+    fn poll_archive_job(&mut self) -> io::Result<()> {
+        let Some(job) = self.archive_job.as_mut() else {
+            return Ok(());
+        };
+        job.poll();
+        let message = match &job.status {
+            JobStatus::Running => return Ok(()),
+            JobStatus::Finished(path) => {
+                let name = path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+                i18n::trf("job_wrote", &[("job", &job.label), ("name", &name)])
+            },
+            JobStatus::Failed(e) => i18n::trf("job_failed", &[("job", &job.label), ("error", e)]),
+        };
+        self.archive_job = None;
+        self.status_message = Some(message);
+        self.refresh();
+        Ok(())
+    }
+
+    /// Carries out the `on_startup` config. Problems end up in the status
+    /// bar rather than stopping the launch.
+    fn run_startup_actions(&mut self) {
+        if let Some(name) = self.config.on_startup.open_tab.clone() {
+            match self.tabs.iter().position(|t| *t == name) {
+                Some(index) => {
+                    if let Err(e) = self.switch_tab(index) {
+                        self.status_message = Some(i18n::trf("cant_open_on_startup", &[("name", &name), ("error", &e)]));
+                    }
+                },
+                None => self.status_message = Some(i18n::trf("no_startup_tab", &[("name", &name)])),
+            }
+        }
+        if let Some(command) = &self.config.on_startup.command {
+            self.startup_command = Some(CommandOutput::spawn(command, &self.current_dir()));
+        }
+    }
+
+    /// Reports the startup command's failure once it exits. Success is silent.
+    fn poll_startup_command(&mut self) {
+        let Some(output) = self.startup_command.as_mut() else {
+            return;
+        };
+        output.poll();
+        let failure = match &output.status {
+            CommandStatus::Running => return,
+            CommandStatus::Exited(Some(0)) => None,
+            CommandStatus::Exited(Some(code)) => Some(i18n::trf("startup_exited", &[("code", code)])),
+            CommandStatus::Exited(None) => Some(i18n::tr("startup_killed")),
+            CommandStatus::Failed(e) => Some(i18n::trf("startup_failed", &[("error", e)])),
+        };
+        if let Some(mut message) = failure {
+            if let Some(line) = output.lines.iter().rev().find(|l| l.stderr && !l.text.trim().is_empty()) {
+                message.push_str(": ");
+                message.push_str(line.text.trim());
+            }
+            self.status_message = Some(message);
+        }
+        self.startup_command = None;
+    }
+
+    /// The first `[[actions]]` key bod already binds itself, which the
+    /// action never gets.
+    fn shadowed_action_key(&self) -> Option<&str> {
+        self.config.actions
+            .iter()
+            .map(|action| action.key.as_str())
+            .find(|key| {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()) {
+                    (Some('f'), None) => !self.config.formatters.is_empty(),
+                    (Some(c), None) => BOUND_KEYS.contains(c),
+                    _ => false,
+                }
+            })
+    }
+
+    /// Runs the `[[actions]]` command bound to `key`, if any, with `{path}`
+    /// and `{dir}` filled in. Returns false when no action uses the key.
+    fn run_action(&mut self, key: char) -> bool {
+        let Some(action) = self.config.actions.iter().find(|a| a.key.chars().eq([key])) else {
+            return false;
+        };
+        let dir = self.current_dir();
+        let path = match self.selected_entry() {
+            Some(entry) => dir.join(&entry.file_name),
+            None => dir.clone(),
+        };
+        let command = action.command
+            .replace("{path}", &shell::quote(&path.to_string_lossy()))
+            .replace("{dir}", &shell::quote(&dir.to_string_lossy()));
+        log::info!("action {}: {}", key, command);
+        let output = CommandOutput::spawn(&command, &dir);
+        if action.show_output {
+            self.command_output = Some(output);
+            self.refresh_after_output = true;
+        } else {
+            self.action_command = Some(output);
+        }
+        true
+    }
+
+    /// Refreshes once an action's command finishes, reporting how it went
+    /// when its output isn't on screen.
+    fn poll_actions(&mut self) -> io::Result<()> {
+        let output_done = self.command_output.as_ref().is_none_or(|o| !matches!(o.status, CommandStatus::Running));
+        if self.refresh_after_output && output_done {
+            self.refresh_after_output = false;
+            self.refresh_panes();
+        }
+        let Some(output) = self.action_command.as_mut() else {
+            return Ok(());
+        };
+        output.poll();
+        let command = &output.command;
+        let mut message = match &output.status {
+            CommandStatus::Running => return Ok(()),
+            CommandStatus::Exited(Some(0)) => i18n::trf("action_done", &[("command", command)]),
+            CommandStatus::Exited(Some(code)) => i18n::trf("action_exited", &[("command", command), ("code", code)]),
+            CommandStatus::Exited(None) => i18n::trf("action_killed", &[("command", command)]),
+            CommandStatus::Failed(e) => i18n::trf("action_failed", &[("command", command), ("error", e)]),
+        };
+        if !matches!(output.status, CommandStatus::Exited(Some(0))) {
+            if let Some(line) = output.lines.iter().rev().find(|l| l.stderr && !l.text.trim().is_empty()) {
+                message.push_str(": ");
+                message.push_str(line.text.trim());
+            }
+        }
+        self.status_message = Some(message);
+        self.action_command = None;
+        // The command may well have changed the file being previewed.
+        self.preview = None;
+        self.refresh_panes();
+        Ok(())
+    }
+
+    /// Runs the `[formatters]` command for the selected file's extension
+    /// on it. Reported like an action's command when it finishes.
+    fn format_selected(&mut self) {
+        let Some(entry) = self.selected_entry() else {
+            return;
+        };
+        let dir = self.current_dir();
+        let path = dir.join(&entry.file_name);
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let Some(formatter) = self.config.formatters.get(&extension).filter(|_| !entry.is_dir) else {
+            self.status_message = Some(i18n::trf("no_formatter", &[("name", &entry.name)]));
+            return;
+        };
+        let command = format!("{} {}", formatter, shell::quote(&path.to_string_lossy()));
+        log::info!("formatting: {}", command);
+        self.action_command = Some(CommandOutput::spawn(&command, &dir));
+    }
+
+    /// Opens the selected file in the diff tool to review or merge its
+    /// changes. Only files git reports as changed are opened.
+    fn diff_selected(&mut self, terminal: &mut launch::Tui) -> io::Result<()> {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return Ok(());
+        };
+        let path = self.current_dir().join(&entry.file_name);
+        let Some(change) = launch::git_change(&path).filter(|_| !entry.is_dir) else {
+            self.status_message = Some(i18n::trf("no_changes", &[("name", &entry.name)]));
+            return Ok(());
+        };
+        if let Err(e) = launch::open_in_diff_tool(terminal, &self.config, &path, change) {
+            self.status_message = Some(i18n::trf("cant_diff", &[("name", &entry.name), ("error", &e)]));
+        }
+        // A merge tool may well have changed the file.
+        self.preview = None;
+        self.refresh_panes();
+        Ok(())
+    }
+
+    /// Hands the selected entry to the OS default application.
+    fn open_selected_with_default_app(&mut self) {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
+        };
+        if entry.broken_link {
+            self.status_message = Some(self.broken_link_message(&entry));
+            return;
+        }
+        let path = self.current_dir().join(&entry.file_name);
+        self.status_message = Some(match launch::open_with_default_app(&path) {
+            Ok(()) => {
+                self.quit = self.config.quit_after_open;
+                i18n::trf("opened", &[("name", &entry.name)])
+            },
+            Err(e) => i18n::trf("cant_open", &[("name", &entry.name), ("error", &e)]),
+        });
+    }
+
+    /// Why `entry`, a broken symlink, can't be opened.
+    fn broken_link_message(&self, entry: &DirEntry) -> String {
+        let target = fs::read_link(self.current_dir().join(&entry.file_name))
+            .map_or_else(|_| "?".to_string(), |target| target.display().to_string());
+        i18n::trf("broken_link_target", &[("name", &entry.name), ("target", &target)])
+    }
+
+    /// Short tags for every listing setting that differs from the default,
+    /// so it's clear why a listing looks the way it does.
+    fn view_modifiers(&self) -> Vec<String> {
+        let mut modifiers = Vec::new();
+        if !self.show_hidden {
+            modifiers.push(i18n::tr("dotfiles_hidden"));
+        }
+        if self.sort_mode != SortMode::Name || self.sort_reverse {
+            let arrow = if self.sort_reverse { "↓" } else { "↑" };
+            modifiers.push(i18n::trf("sort_modifier", &[("mode", &self.sort_mode.label()), ("arrow", &arrow)]));
+        }
+        if let Some(filter) = &self.filter {
+            let key = if self.search_case_sensitive { "filter_modifier_case" } else { "filter_modifier" };
+            modifiers.push(i18n::trf(key, &[("filter", filter)]));
+        }
+        modifiers
+    }
+
+    /// Re-reads the current directory, keeping the same entry selected (see
+    /// `apply_view`). Falls back to the tab root if the directory has
+    /// disappeared, and to the overview if the root has too. Since it runs
+    /// on timers, it never fails.
+    fn refresh(&mut self) {
+        let result = if !self.current_dir().is_dir() {
+            // Select the way back down rather than whatever shares an index
+            // with the old selection.
+            let top = self.pane.current_subpath.components().next().map(|c| c.as_os_str().to_os_string());
+            self.pane.current_subpath = PathBuf::new();
+            self.pane.selected_item = None;
+            let result = self.update_current_dir_contents();
+            if let Some(top) = top {
+                self.select_name(&top, None);
+            }
+            result
+        } else {
+            self.update_current_dir_contents()
+        };
+        if let Err(e) = result {
+            self.fall_back_to_overview(e);
+        }
+        // The selected file may have changed too.
+        self.preview = None;
+        self.last_refresh = Instant::now();
+    }
+
+    /// Lists the projects overview instead of a directory that can't be
+    /// read any more, such as a tab whose project was deleted, and says
+    /// why. The listing is left empty if the projects directory is gone
+    /// as well.
+    fn fall_back_to_overview(&mut self, error: io::Error) {
+        log::warn!("can't list {}: {}", self.current_dir().display(), error);
+        self.status_message = Some(i18n::trf("cant_list", &[("path", &self.current_dir().display()), ("error", &error)]));
+        self.pane.archive = None;
+        self.pane.free_root = Some(self.base_path.clone());
+        self.pane.current_subpath = PathBuf::new();
+        self.pane.selected_item = None;
+        if self.update_current_dir_contents().is_err() {
+            self.pane.all_dir_contents.clear();
+            self.pane.current_dir_contents.clear();
+        }
+    }
+
+    /// True once nothing has happened for `idle_dim_secs`.
+    fn is_idle(&self) -> bool {
+        self.config.idle_dim_secs.is_some_and(|secs| self.last_input.elapsed() >= Duration::from_secs(secs))
+    }
+
+    /// `refresh` for both panes in dual-pane mode.
+    fn refresh_panes(&mut self) {
+        self.refresh();
+        if self.other_pane.is_some() {
+            self.with_other_pane(App::refresh);
+        }
+    }
+
+    /// Refreshes the listing when `auto_refresh_secs` has elapsed.
+    fn auto_refresh(&mut self) {
+        let Some(secs) = self.config.auto_refresh_secs else {
+            return;
+        };
+        if self.last_refresh.elapsed() >= Duration::from_secs(secs) {
+            self.refresh();
+        }
+    }
+
+    /// Checks the free space again every `usage::FREE_SPACE_SECS`.
+    fn update_free_space(&mut self) {
+        if !self.config.show_free_space
+            || self.free_space_checked.is_some_and(|at| at.elapsed() < Duration::from_secs(usage::FREE_SPACE_SECS))
+        {
+            return;
+        }
+        self.free_space_checked = Some(Instant::now());
+        self.free_space = match usage::free_space(&self.base_path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                log::warn!("couldn't check free space: {}", e);
+                None
+            },
+        };
+    }
+
+    /// Writes the session every few seconds while it differs from what's
+    /// on disk, so closing the terminal without quitting keeps it.
+    fn autosave_session(&mut self) {
+        if self.last_session_check.elapsed() < Duration::from_secs(session::AUTOSAVE_SECS) {
+            return;
+        }
+        self.last_session_check = Instant::now();
+        if self.visits_changed {
+            match self.visits.save() {
+                Ok(()) => self.visits_changed = false,
+                Err(e) => log::warn!("couldn't save visits: {}", e),
+            }
+        }
+        let session = self.session();
+        if session == self.saved_session {
+            return;
+        }
+        match session.save() {
+            Ok(()) => self.saved_session = session,
+            Err(e) => log::warn!("couldn't save session: {}", e),
+        }
+    }
+
+    fn switch_tab(&mut self, tab_index: usize) -> io::Result<()> {
+        if tab_index < self.tabs.len() {
+            let same_tab = self.current_tab_name().is_some() && self.pane.current_tab == tab_index;
+            self.remember_tab_subpath();
+            self.pane.free_root = None;
+            self.pane.current_tab = tab_index;
+            // Switching to the tab already shown goes back to its root.
+            self.pane.current_subpath = if same_tab { PathBuf::new() } else { self.remembered_subpath(tab_index) };
+            if !same_tab {
+                self.visits.record(&self.tabs[tab_index]);
+                self.visits_changed = true;
+            }
+            self.pane.selected_item = None;
+            self.update_current_dir_contents()?;
+            self.record_navigation();
+        }
+        Ok(())
+    }
+
+    /// Switches to the tab a tab key points at. Past the last tab, either
+    /// says so or goes to the last one, as `tab_key_past_end` says.
+    fn switch_tab_by_key(&mut self, tab_index: usize) -> io::Result<()> {
+        if tab_index < self.tabs.len() {
+            return self.switch_tab(tab_index);
+        }
+        if self.config.tab_key_past_end == config::TabKeyPastEnd::Last && !self.tabs.is_empty() {
+            return self.switch_tab(self.tabs.len() - 1);
+        }
+        self.status_message = Some(i18n::trf("no_tab", &[("name", &(tab_index + 1))]));
+        Ok(())
+    }
+
+    /// Switches to the next or previous tab, wrapping around at either end.
+    /// From outside the tabs, starts at the first or last.
+    fn cycle_tab(&mut self, forward: bool) -> io::Result<()> {
+        let len = self.tabs.len();
+        if len == 0 {
+            return Ok(());
+        }
+        let target = match (self.current_tab_name().is_some(), forward) {
+            (true, true) => (self.pane.current_tab + 1) % len,
+            (true, false) => (self.pane.current_tab + len - 1) % len,
+            (false, true) => 0,
+            (false, false) => len - 1,
+        };
+        self.switch_tab(target)
+    }
+
+    /// Notes where the current tab was left, for `remembered_subpath`.
+    fn remember_tab_subpath(&mut self) {
+        if !self.config.remember_tab_paths {
+            return;
+        }
+        if let Some(tab) = self.current_tab_name().cloned() {
+            self.tab_subpaths.insert(tab, self.pane.current_subpath.clone());
+        }
+    }
+
+    /// Where to open tab `tab_index`: the subdirectory it was left in if
+    /// that still exists, otherwise its root.
+    fn remembered_subpath(&self, tab_index: usize) -> PathBuf {
+        let Some(tab) = self.tabs.get(tab_index) else {
+            return PathBuf::new();
+        };
+        match self.tab_subpaths.get(tab) {
+            Some(subpath) if self.config.remember_tab_paths && self.tab_root(tab).join(subpath).is_dir() => {
+                subpath.clone()
+            },
+            _ => PathBuf::new(),
+        }
+    }
+
+    /// Goes to the root of the `default_project` tab, or of the first tab
+    /// if that isn't set or has no tab.
+    fn go_home(&mut self) -> io::Result<()> {
+        if self.tabs.is_empty() {
+            return Ok(());
+        }
+        let name = self.config.default_project.as_ref();
+        let index = match name.map(|name| (name, self.tabs.iter().position(|t| t == name))) {
+            Some((_, Some(index))) => index,
+            Some((name, None)) => {
+                self.status_message = Some(i18n::trf("no_default_project", &[("name", name)]));
+                0
+            },
+            None => 0,
+        };
+        self.remember_tab_subpath();
+        self.pane.free_root = None;
+        self.pane.current_tab = index;
+        self.pane.current_subpath = PathBuf::new();
+        self.pane.selected_item = None;
+        self.update_current_dir_contents()?;
+        self.record_navigation();
+        Ok(())
+    }
+
+    /// Lists the projects directory itself, including projects whose tabs
+    /// are hidden.
+    fn open_overview(&mut self) -> io::Result<()> {
+        self.remember_tab_subpath();
+        self.pane.free_root = Some(self.base_path.clone());
+        self.pane.current_subpath = PathBuf::new();
+        self.pane.selected_item = None;
+        self.update_current_dir_contents()?;
+        self.record_navigation();
+        Ok(())
+    }
+
+    /// Switches from the overview to the project `name`, restoring its tab
+    /// if it was hidden and picking it up if it was created since startup.
+    fn open_project(&mut self, name: &OsStr) -> io::Result<()> {
+        match self.ensure_tab(name)? {
+            Some(index) => self.switch_tab(index),
+            None => Ok(()),
+        }
+    }
+
+    /// Index of the tab for project `name`, restoring it if hidden and
+    /// rediscovering tabs if it's new. `None` if it isn't a project.
+    fn ensure_tab(&mut self, name: &OsStr) -> io::Result<Option<usize>> {
+        let name = name.to_string_lossy().into_owned();
+        if let Some(index) = self.config.hidden_tabs.iter().position(|t| *t == name) {
+            self.unhide_tab(index)?;
+        } else if !self.tabs.contains(&name) {
+            self.rediscover_tabs()?;
+        }
+        Ok(self.tabs.iter().position(|t| *t == name))
+    }
+
+    /// Jumps to `text`, an absolute, `~/` or relative path. Inside the
+    /// projects directory this lands on the right tab; anywhere else the
+    /// directory becomes a free root. A file is shown selected in its
+    /// directory.
+    fn go_to_path(&mut self, text: &str) -> io::Result<()> {
+        let path = self.current_dir().join(Path::new(text).expand_home()?);
+        let mut path = fs::canonicalize(path)?;
+        let mut file = None;
+        if !path.is_dir() {
+            file = path.file_name().map(OsStr::to_os_string);
+            path.pop();
+        }
+
+        self.remember_tab_subpath();
+        let base = fs::canonicalize(&self.base_path).unwrap_or_else(|_| self.base_path.clone());
+        let mut components = path.strip_prefix(&base).ok().map(|rest| rest.components());
+        match components.as_mut().and_then(|c| c.next()) {
+            Some(project) => {
+                let Some(index) = self.ensure_tab(project.as_os_str())? else {
+                    return Err(io::Error::new(io::ErrorKind::NotFound, "not a project directory"));
+                };
+                self.pane.free_root = None;
+                self.pane.current_tab = index;
+                self.pane.current_subpath = components.map(|c| c.as_path().to_path_buf()).unwrap_or_default();
+            },
+            None if components.is_some() => {
+                self.pane.free_root = Some(self.base_path.clone());
+                self.pane.current_subpath = PathBuf::new();
+            },
+            None => {
+                self.pane.free_root = Some(path);
+                self.pane.current_subpath = PathBuf::new();
+            },
+        }
+        self.pane.selected_item = None;
+        self.update_current_dir_contents()?;
+        if let Some(file) = file {
+            self.select_name(&file, None);
+        }
+        self.record_navigation();
+        Ok(())
+    }
+
+    fn open_input(&mut self, prompt: Prompt) {
+        let text = match prompt {
+            Prompt::Filter => self.filter.clone().unwrap_or_default(),
+            Prompt::TabLabel => self.new_tab_dir()
+                .and_then(|dir| dir.file_name().map(|name| name.to_string_lossy().into_owned()))
+                .unwrap_or_default(),
+            Prompt::Command | Prompt::GoTo | Prompt::TabNumber | Prompt::NewProject | Prompt::Export => String::new(),
+        };
+        self.input = Some(Input { prompt, text, completion: None });
+    }
+
+    /// Writes the listing as shown, in `export_format`, to the file at
+    /// `target`, or to the clipboard when `target` is empty.
+    fn export_listing(&mut self, target: &str) {
+        let rows: Vec<_> = self.pane.current_dir_contents
+            .iter()
+            .filter(|e| !e.parent)
+            .map(|e| export::Row { name: &e.name, is_dir: e.is_dir, size: e.size })
+            .collect();
+        let text = export::render(&rows, self.config.export_format);
+        let result = match target {
+            "" => export::to_clipboard(&text).map(|()| i18n::tr("exported_clipboard")),
+            _ => Path::new(target)
+                .expand_home()
+                .and_then(|path| export::to_file(&text, &self.current_dir().join(path)))
+                .map(|()| i18n::trf("exported", &[("path", &target)])),
+        };
+        self.status_message = Some(result.unwrap_or_else(|e| i18n::trf("cant_export", &[("error", &e)])));
+    }
+
+    fn set_filter(&mut self, filter: &str) {
+        self.filter = (!filter.is_empty()).then(|| filter.to_string());
+        self.apply_view();
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        let cwd = self.current_dir();
+        let Some(input) = self.input.as_mut() else {
+            return Ok(());
+        };
+        if key.code != KeyCode::Tab {
+            input.completion = None;
+        }
+        match key.code {
+            KeyCode::Esc => {
+                if input.prompt == Prompt::Filter {
+                    self.set_filter("");
+                }
+                self.input = None;
+            },
+            KeyCode::Enter => {
+                if let Some(input) = self.input.take() {
+                    self.submit_input(input)?;
+                }
+            },
+            KeyCode::Backspace => {
+                input.text.pop();
+            },
+            KeyCode::Tab if input.prompt == Prompt::Filter => {
+                self.search_case_sensitive = !self.search_case_sensitive;
+            },
+            KeyCode::Tab => match input.completion.as_mut() {
+                Some(completion) => completion.cycle(&mut input.text),
+                None => {
+                    let candidates = match input.prompt {
+                        Prompt::GoTo => complete::path_completions(&input.text, &cwd, true),
+                        Prompt::Export => complete::path_completions(&input.text, &cwd, false),
+                        Prompt::Command => complete::command_completions(&input.text, &cwd),
+                        Prompt::Filter | Prompt::TabNumber | Prompt::TabLabel | Prompt::NewProject => Vec::new(),
+                    };
+                    input.completion = Completion::start(&mut input.text, candidates);
+                },
+            },
+            KeyCode::Char(c) if input.prompt == Prompt::TabNumber => {
+                if c.is_ascii_digit() {
+                    input.text.push(c);
+                }
+                // Jump as soon as another digit couldn't name a tab.
+                let number: usize = input.text.parse().unwrap_or(0);
+                if number > 0 && number * 10 > self.tabs.len() {
+                    if let Some(input) = self.input.take() {
+                        self.submit_input(input)?;
+                    }
+                }
+            },
+            KeyCode::Char(c) => input.text.push(c),
+            _ => {},
+        }
+        // The filter applies live while it's being typed.
+        if let Some(input) = &self.input {
+            if input.prompt == Prompt::Filter {
+                let text = input.text.clone();
+                self.set_filter(&text);
+            }
+        }
+        Ok(())
+    }
+
+    fn submit_input(&mut self, input: Input) -> io::Result<()> {
+        let text = input.text.trim();
+        if input.prompt == Prompt::Filter {
+            self.set_filter(text);
+            return Ok(());
+        }
+        if input.prompt == Prompt::Export {
+            self.export_listing(text);
+            return Ok(());
+        }
+        if text.is_empty() {
+            return Ok(());
+        }
+        match input.prompt {
+            Prompt::Filter | Prompt::Export => {},
+            Prompt::Command => {
+                self.command_output = Some(CommandOutput::spawn(text, &self.current_dir()));
+            },
+            Prompt::GoTo => {
+                if let Err(e) = self.go_to_path(text) {
+                    self.status_message = Some(i18n::trf("cant_go_to", &[("path", &text), ("error", &e)]));
+                }
+            },
+            Prompt::TabNumber => match text.parse::<usize>() {
+                Ok(number) if (1..=self.tabs.len()).contains(&number) => self.switch_tab(number - 1)?,
+                _ => self.status_message = Some(i18n::trf("no_tab", &[("name", &text)])),
+            },
+            Prompt::TabLabel => self.add_custom_tab(text)?,
+            Prompt::NewProject => self.create_project(text)?,
+        }
+        Ok(())
+    }
+
+    /// True when there's no tab to show and the pane isn't browsing
+    /// somewhere else, as on a fresh projects directory.
+    fn has_no_projects(&self) -> bool {
+        self.tabs.is_empty() && self.pane.free_root.is_none()
+    }
+
+    /// Makes the directory `name` in the projects directory and switches
+    /// to its tab.
+    fn create_project(&mut self, name: &str) -> io::Result<()> {
+        if name.contains(std::path::is_separator) || name == "." || name == ".." {
+            self.status_message = Some(i18n::trf("bad_project_name", &[("name", &name)]));
+            return Ok(());
+        }
+        if let Err(e) = fs::create_dir(self.base_path.join(name)) {
+            self.status_message = Some(i18n::trf("cant_create_project", &[("name", &name), ("error", &e)]));
+            return Ok(());
+        }
+        if let Some(index) = self.ensure_tab(OsStr::new(name))? {
+            self.switch_tab(index)?;
+        }
+        Ok(())
+    }
+
+    /// The directory `A` turns into a tab: the selected entry if it's a
+    /// directory, otherwise the one being listed.
+    fn new_tab_dir(&self) -> Option<PathBuf> {
+        if self.in_overview() {
+            return None;
+        }
+        let selected = self.selected_entry().filter(|e| e.is_dir);
+        Some(match selected {
+            Some(entry) => self.current_dir().join(&entry.file_name),
+            None => self.current_dir(),
+        })
+    }
+
+    /// Saves `new_tab_dir` as a custom tab called `label` and switches to it.
+    fn add_custom_tab(&mut self, label: &str) -> io::Result<()> {
+        let Some(dir) = self.new_tab_dir() else {
+            return Ok(());
+        };
+        if self.tabs.iter().any(|t| t == label) || self.config.custom_tabs.contains_key(label) {
+            self.status_message = Some(i18n::trf("tab_exists", &[("name", &label)]));
+            return Ok(());
+        }
+        let Some(path) = dir.to_str() else {
+            self.status_message = Some(i18n::tr("tab_not_utf8"));
+            return Ok(());
+        };
+        self.config.custom_tabs.insert(label.to_string(), path.to_string());
+        if let Err(e) = config::set_key("custom_tabs", config::string_table(&self.config.custom_tabs)) {
+            self.status_message = Some(i18n::trf("cant_save_tab", &[("name", &label), ("error", &e)]));
+        }
+        self.rediscover_tabs()?;
+        if let Some(index) = self.tabs.iter().position(|t| t == label) {
+            self.switch_tab(index)?;
+        }
+        Ok(())
+    }
+
+    fn handle_output_key(&mut self, key: KeyEvent) {
+        let Some(output) = self.command_output.as_mut() else {
+            return;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => self.command_output = None,
+            KeyCode::Up => output.scroll_up(1),
+            KeyCode::Down => output.scroll_down(1),
+            KeyCode::PageUp => output.scroll_up(10),
+            KeyCode::PageDown => output.scroll_down(10),
+            _ => {},
+        }
+    }
+
+    /// Swaps the current tab with its neighbour and saves the new order.
+    fn move_tab(&mut self, forward: bool) {
+        if self.pane.free_root.is_some() {
+            return;
+        }
+        let target = if forward {
+            self.pane.current_tab + 1
+        } else {
+            match self.pane.current_tab.checked_sub(1) {
+                Some(target) => target,
+                None => return,
+            }
+        };
+        if target >= self.tabs.len() {
+            return;
+        }
+        let other_tab = self.other_pane_tab();
+        self.tabs.swap(self.pane.current_tab, target);
+        self.pane.current_tab = target;
+        if let Err(e) = self.retarget_other_pane(other_tab) {
+            self.status_message = Some(e.to_string());
+        }
+        self.config.tab_order = TabOrder::Names(self.tabs.clone());
+        if let Err(e) = config::set_key("tab_order", config::string_list(&self.tabs)) {
+            self.status_message = Some(i18n::trf("cant_save_tab_order", &[("error", &e)]));
+        }
+    }
+
+    /// Hides the current tab and remembers it in `hidden_tabs`.
+    fn hide_current_tab(&mut self) -> io::Result<()> {
+        let Some(name) = self.current_tab_name().cloned() else {
+            return Ok(());
+        };
+        self.config.hidden_tabs.push(name.clone());
+        let other_tab = self.other_pane_tab();
+        self.tabs.remove(self.pane.current_tab);
+        self.tab_subpaths.remove(&name);
+        // Not switch_tab, which would remember this subpath for the
+        // neighbour now at this index.
+        self.pane.current_tab = self.pane.current_tab.min(self.tabs.len().saturating_sub(1));
+        self.pane.current_subpath = self.remembered_subpath(self.pane.current_tab);
+        self.pane.selected_item = None;
+        self.update_current_dir_contents()?;
+        self.record_navigation();
+        self.retarget_other_pane(other_tab)?;
+        self.status_message = Some(i18n::trf("hid_tab", &[("name", &name)]));
+        self.save_hidden_tabs();
+        Ok(())
+    }
+
+    /// Un-hides the tab at `index` in `hidden_tabs` and rediscovers tabs so
+    /// it reappears in its usual place.
+    fn unhide_tab(&mut self, index: usize) -> io::Result<()> {
+        if index >= self.config.hidden_tabs.len() {
+            return Ok(());
+        }
+        let name = self.config.hidden_tabs.remove(index);
+        self.rediscover_tabs()?;
+        self.status_message = Some(i18n::trf("restored_tab", &[("name", &name)]));
+        self.save_hidden_tabs();
+        Ok(())
+    }
+
+    /// Reads the manifest of every tab that hasn't been looked at yet.
+    fn load_manifests(&mut self) {
+        for tab in &self.tabs {
+            if !self.manifests.contains_key(tab) {
+                if let Some(info) = ProjectInfo::detect(&self.tab_root(tab)) {
+                    self.manifests.insert(tab.clone(), info);
+                }
+            }
+        }
+    }
+
+    /// Says which project directories `discover_tabs` couldn't make tabs of.
+    fn report_skipped_tabs(&mut self, skipped: &[String]) {
+        let name = match skipped {
+            [] => return,
+            [name] => name.clone(),
+            [name, rest @ ..] => i18n::trf("name_and_more", &[("name", name), ("count", &rest.len())]),
+        };
+        self.status_message = Some(i18n::trf("tab_skipped_not_utf8", &[("name", &name)]));
+    }
+
+    /// Re-runs tab discovery, staying on the current tab if it's still there.
+    fn rediscover_tabs(&mut self) -> io::Result<()> {
+        let other_tab = self.other_pane_tab();
+        let (tabs, skipped) = discover_tabs(&self.base_path, &self.config, &self.frecent_tabs)?;
+        self.report_skipped_tabs(&skipped);
+        if self.pane.free_root.is_some() {
+            self.tabs = tabs;
+            self.load_manifests();
+            self.pane.current_tab = self.pane.current_tab.min(self.tabs.len().saturating_sub(1));
+            return self.retarget_other_pane(other_tab);
+        }
+        let current = self.tabs.get(self.pane.current_tab).cloned();
+        self.tabs = tabs;
+        self.load_manifests();
+        match current.and_then(|c| self.tabs.iter().position(|t| *t == c)) {
+            Some(index) => self.pane.current_tab = index,
+            None => {
+                self.pane.current_tab = 0;
+                self.pane.current_subpath = PathBuf::new();
+                self.pane.selected_item = None;
+                self.update_current_dir_contents()?;
+            },
+        }
+        self.retarget_other_pane(other_tab)
+    }
+
+    /// The tab the unfocused pane is on, if any.
+    fn other_pane_tab(&self) -> Option<String> {
+        self.other_pane.as_ref().and_then(|pane| self.tab_name_of(pane)).cloned()
+    }
+
+    /// Keeps the unfocused pane on tab `name` after the tab list changes
+    /// under it, or sends it to the first tab if `name` is gone.
+    fn retarget_other_pane(&mut self, name: Option<String>) -> io::Result<()> {
+        let Some(name) = name else {
+            return Ok(());
+        };
+        match self.tabs.iter().position(|t| *t == name) {
+            Some(index) => {
+                if let Some(other) = self.other_pane.as_mut() {
+                    other.current_tab = index;
+                }
+                Ok(())
+            },
+            None => self.with_other_pane(|app| {
+                app.pane.current_tab = 0;
+                app.pane.current_subpath = PathBuf::new();
+                app.pane.selected_item = None;
+                app.update_current_dir_contents()
+            }),
+        }
+    }
+
+    /// Opens the config file in the editor, creating it with commented
+    /// defaults first if needed. A foreground editor is waited for and the
+    /// config reloaded straight away; otherwise the reload happens when the
+    /// terminal next regains focus.
+    fn edit_config(&mut self, terminal: &mut launch::Tui) -> io::Result<()> {
+        let result = Config::ensure_exists()
+            .and_then(|path| launch::open_in_editor(terminal, &self.config, None, &path, None));
+        if self.config_watcher.is_none() {
+            // The config directory may only just have been created.
+            self.config_watcher = ConfigWatcher::start();
+        }
+        match (result, self.config.launch_mode) {
+            (Err(e), _) => self.status_message = Some(i18n::trf("cant_open_config", &[("error", &e)])),
+            (Ok(()), LaunchMode::Foreground) => self.reload_config()?,
+            (Ok(()), LaunchMode::NewWindow) => self.config_reload_pending = true,
+        }
+        Ok(())
+    }
+
+    /// Re-reads the config file and applies it to the running app. A file
+    /// that fails to parse leaves the current settings in place.
+    fn reload_config(&mut self) -> io::Result<()> {
+        self.config_reload_pending = false;
+        match Config::load() {
+            Ok(config) => {
+                self.theme = Theme::for_terminal(config.theme);
+                self.clock = Clock::new(&config);
+                self.graphics = config.image_previews && graphics::supported();
+                let catalog = i18n::load(config.locale.as_deref());
+                self.config = config;
+                self.rediscover_tabs()?;
+                let color_rules = self.load_color_rules();
+                self.status_message = Some(match (catalog, color_rules, self.shadowed_action_key()) {
+                    (Err(e), _, _) => i18n::trf("catalog_not_loaded", &[("error", &e)]),
+                    (_, Err(e), _) => e.to_string(),
+                    (_, _, Some(key)) => i18n::trf("action_key_taken", &[("key", &key)]),
+                    (Ok(()), Ok(()), None) => i18n::tr("config_reloaded"),
+                });
+            },
+            Err(e) => self.status_message = Some(i18n::trf("config_not_reloaded", &[("error", &e)])),
+        }
+        Ok(())
+    }
+
+    /// Compiles the config's `color_rules`. If any is invalid none apply.
+    fn load_color_rules(&mut self) -> io::Result<()> {
+        match ColorRules::new(&self.config.color_rules) {
+            Ok(rules) => {
+                self.color_rules = rules;
+                Ok(())
+            },
+            Err(e) => {
+                self.color_rules = ColorRules::default();
+                Err(e)
+            },
+        }
+    }
+
+    /// Reloads the config if the watcher saw it change on disk.
+    fn poll_config_watcher(&mut self) -> io::Result<()> {
+        if self.config_watcher.as_ref().is_some_and(ConfigWatcher::changed) {
+            self.reload_config()?;
+        }
+        Ok(())
+    }
+
+    fn handle_focus_gained(&mut self) -> io::Result<()> {
+        if self.config_reload_pending {
+            self.reload_config()?;
+        }
+        // Pick up edits made to the note in a separate editor window.
+        if self.note_popup.is_some() {
+            self.open_note();
+        }
+        // And to files, made wherever focus just came back from.
+        if self.config.refresh_on_focus {
+            self.refresh_panes();
+        }
+        Ok(())
+    }
+
+    fn save_hidden_tabs(&mut self) {
+        if let Err(e) = config::set_key("hidden_tabs", config::string_list(&self.config.hidden_tabs)) {
+            self.status_message = Some(i18n::trf("cant_save_hidden_tabs", &[("error", &e)]));
+        }
+    }
+
+    fn handle_hidden_tabs_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        let Some(selected) = self.hidden_tabs_popup else {
+            return Ok(());
+        };
+        let len = self.config.hidden_tabs.len();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('U') => self.hidden_tabs_popup = None,
+            KeyCode::Up => self.hidden_tabs_popup = Some(selected.saturating_sub(1)),
+            KeyCode::Down if selected + 1 < len => self.hidden_tabs_popup = Some(selected + 1),
+            KeyCode::Enter => {
+                self.unhide_tab(selected)?;
+                let len = self.config.hidden_tabs.len();
+                self.hidden_tabs_popup = if len == 0 { None } else { Some(selected.min(len - 1)) };
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// Opens the current project's note popup, or re-reads it if it's
+    /// already open.
+    fn open_note(&mut self) {
+        let Some(tab) = self.current_tab_name() else {
+            return;
+        };
+        match notes::read(tab) {
+            Ok(text) => self.note_popup = Some(text),
+            Err(e) => self.status_message = Some(i18n::trf("cant_read_note", &[("error", &e)])),
+        }
+    }
+
+    /// Opens the current project's note in the editor, then shows the
+    /// result if the editor ran in the foreground.
+    fn edit_note(&mut self, terminal: &mut launch::Tui) {
+        let Some(tab) = self.current_tab_name() else {
+            return;
+        };
+        let result = notes::ensure_exists(tab)
+            .and_then(|path| launch::open_in_editor(terminal, &self.config, None, &path, None));
+        if let Err(e) = result {
+            self.status_message = Some(i18n::trf("cant_open_note", &[("error", &e)]));
+        }
+        self.open_note();
+    }
+
+    fn handle_note_key(&mut self, key: KeyEvent, terminal: &mut launch::Tui) {
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('N') => self.note_popup = None,
+            KeyCode::Char('e') => self.edit_note(terminal),
+            _ => {},
+        }
+    }
+
+    /// Indexes of the tabs matching the tab search query, best first.
+    fn tab_search_matches(&self, query: &str) -> Vec<usize> {
+        let mut matches: Vec<(i64, usize)> = self.tabs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, tab)| fuzzy::score(query, tab, false).map(|score| (score, i)))
+            .collect();
+        // Stable, so equal scores keep tab order.
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.into_iter().map(|(_, i)| i).collect()
+    }
+
+    fn handle_tab_search_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        let Some(search) = self.tab_search.as_mut() else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.tab_search = None,
+            KeyCode::Up => search.selected = search.selected.saturating_sub(1),
+            KeyCode::Down => search.selected += 1,
+            KeyCode::Backspace => {
+                search.query.pop();
+                search.selected = 0;
+            },
+            KeyCode::Char(c) => {
+                search.query.push(c);
+                search.selected = 0;
+            },
+            KeyCode::Enter => {
+                let query = search.query.clone();
+                let selected = search.selected;
+                self.tab_search = None;
+                let matches = self.tab_search_matches(&query);
+                if let Some(&index) = matches.get(selected.min(matches.len().saturating_sub(1))) {
+                    self.switch_tab(index)?;
+                }
+            },
+            _ => {},
+        }
+        // Keep the highlight on a real row.
+        let count = self.tab_search.as_ref().map(|search| self.tab_search_matches(&search.query).len());
+        if let (Some(search), Some(count)) = (self.tab_search.as_mut(), count) {
+            search.selected = search.selected.min(count.saturating_sub(1));
+        }
+        Ok(())
+    }
+
+    /// Opens the selected directory, or the one being listed, in the
+    /// editor as a workspace.
+    fn open_dir_in_editor(&mut self, terminal: &mut launch::Tui) {
+        let selected = self.selected_entry().filter(|e| e.is_dir);
+        let path = match selected {
+            Some(entry) => self.current_dir().join(&entry.file_name),
+            None => self.current_dir(),
+        };
+        let name = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().into_owned());
+        self.status_message = Some(match launch::open_dir_in_editor(terminal, &self.config, self.current_tab_name().map(String::as_str), &path) {
+            Ok(()) => {
+                self.quit = self.config.quit_after_open;
+                i18n::trf("opened_with", &[("name", &name), ("handler", &launch::editor_name(&self.config))])
+            },
+            Err(e) => i18n::trf("cant_open", &[("name", &name), ("error", &e)]),
+        });
+    }
+
+    /// Lists the newest files across every tab, walking them afresh.
+    fn open_recent(&mut self) {
+        let roots: Vec<(String, PathBuf)> = self.tabs.iter().map(|tab| (tab.clone(), self.tab_root(tab))).collect();
+        let depth = self.config.recent_depth.unwrap_or(config::DEFAULT_RECENT_DEPTH);
+        self.recent = Some(RecentView { files: recent::scan(&roots, depth), selected: 0 });
+    }
+
+    fn handle_recent_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        let Some(view) = self.recent.as_mut() else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.recent = None,
+            KeyCode::Char('r') => self.open_recent(),
+            KeyCode::Up => view.selected = view.selected.saturating_sub(1),
+            KeyCode::Down if view.selected + 1 < view.files.len() => view.selected += 1,
+            KeyCode::Enter if view.selected < view.files.len() => {
+                let file = view.files.swap_remove(view.selected);
+                self.recent = None;
+                self.go_to_file(&file.tab, &file.path)?;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// Opens the file finder on the current tab, walking it the first time.
+    fn open_finder(&mut self) {
+        let Some(tab) = self.current_tab_name().cloned() else {
+            self.status_message = Some(i18n::tr("finder_needs_tab"));
+            return;
+        };
+        if !self.finder_files.contains_key(&tab) {
+            self.finder_files.insert(tab.clone(), finder::scan(&self.tab_root(&tab)));
+        }
+        let matches = finder::matches(&self.finder_files[&tab], "");
+        self.finder = Some(FileFinder { tab, query: String::new(), matches, selected: 0 });
+    }
+
+    fn handle_finder_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        let Some(root) = self.finder.as_ref().map(|finder| self.tab_root(&finder.tab)) else {
+            return Ok(());
+        };
+        let Some(finder) = self.finder.as_mut() else {
+            return Ok(());
+        };
+        let files = self.finder_files.entry(finder.tab.clone()).or_default();
+        match key.code {
+            KeyCode::Esc => self.finder = None,
+            KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                *files = finder::scan(&root);
+                finder.matches = finder::matches(files, &finder.query);
+                finder.selected = 0;
+            },
+            KeyCode::Up => finder.selected = finder.selected.saturating_sub(1),
+            KeyCode::Down if finder.selected + 1 < finder.matches.len() => finder.selected += 1,
+            KeyCode::Backspace => {
+                finder.query.pop();
+                finder.matches = finder::matches(files, &finder.query);
+                finder.selected = 0;
+            },
+            KeyCode::Char(c) => {
+                finder.query.push(c);
+                finder.matches = finder::matches(files, &finder.query);
+                finder.selected = 0;
+            },
+            KeyCode::Enter => {
+                let picked = finder.matches.get(finder.selected).and_then(|&i| files.get(i)).cloned();
+                let tab = finder.tab.clone();
+                self.finder = None;
+                if let Some(path) = picked {
+                    self.go_to_file(&tab, &path)?;
+                }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    /// Switches to `tab` and the directory of `path`, relative to the tab
+    /// root, with the file selected.
+    fn go_to_file(&mut self, tab: &str, path: &Path) -> io::Result<()> {
+        let Some(index) = self.tabs.iter().position(|t| t == tab) else {
+            return Ok(());
+        };
+        self.remember_tab_subpath();
+        self.pane.free_root = None;
+        self.pane.current_tab = index;
+        self.pane.current_subpath = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        self.pane.selected_item = None;
+        self.update_current_dir_contents()?;
+        if let Some(name) = path.file_name() {
+            self.select_name(name, None);
+        }
+        self.record_navigation();
+        self.show_content = true;
+        Ok(())
+    }
 
-impl App {
-    fn new() -> io::Result<App> {
-        let path = Path::new("~/Documents/rakesh/projects").expand_home()?;
-        let mut tabs = Vec::new();
-        
-        for entry in fs::read_dir(path)? {
-            if let Ok(entry) = entry {
-                if entry.file_type()?.is_dir() {
-                    tabs.push(entry.file_name().to_string_lossy().into_owned());
+    fn handle_tab_switcher_key(&mut self, key: KeyEvent) -> io::Result<()> {
+        let Some(pick) = self.tab_switcher else {
+            return Ok(());
+        };
+        match key.code {
+            KeyCode::Esc => self.tab_switcher = None,
+            KeyCode::Char(c) => {
+                let c = c.to_ascii_lowercase();
+                if let Some(index) = tab_hotkeys(&self.tabs).iter().position(|k| *k == Some(c)) {
+                    self.tab_switcher = None;
+                    match pick {
+                        TabPick::Switch => self.switch_tab(index)?,
+                        TabPick::CopyTo => self.copy_to_tab(index)?,
+                    }
                 }
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    fn nav_state(&self) -> Option<NavState> {
+        if let Some(root) = &self.pane.free_root {
+            return Some(NavState { root: NavRoot::Dir(root.clone()), subpath: self.pane.current_subpath.clone() });
+        }
+        self.tabs.get(self.pane.current_tab).map(|tab| NavState {
+            root: NavRoot::Tab(tab.clone()),
+            subpath: self.pane.current_subpath.clone(),
+        })
+    }
+
+    fn record_navigation(&mut self) {
+        log::debug!("navigated to {}", self.current_dir().display());
+        if let Some(state) = self.nav_state() {
+            self.pane.history.push(state);
+        }
+    }
+
+    /// Descends into the named subdirectory of the current directory.
+    fn enter_dir(&mut self, name: &OsStr) -> io::Result<()> {
+        if let Some(view) = self.pane.archive.as_mut() {
+            view.inner.push(name);
+            self.pane.selected_item = None;
+            return self.update_current_dir_contents();
+        }
+        self.pane.current_subpath.push(name);
+        self.pane.selected_item = None;
+        if let Err(e) = self.update_current_dir_contents() {
+            self.pane.current_subpath.pop();
+            return Err(e);
+        }
+        self.record_navigation();
+        Ok(())
+    }
+
+    /// Moves up one level, keeping the directory we came from selected.
+    /// From the top of an archive, that's back out to the archive itself.
+    /// Above the top of a free root (other than the overview) the parent
+    /// becomes the new root.
+    fn leave_dir(&mut self) -> io::Result<()> {
+        if let Some(view) = self.pane.archive.as_mut() {
+            let child = match view.inner.file_name().map(OsStr::to_os_string) {
+                Some(child) => {
+                    view.inner.pop();
+                    child
+                },
+                // At the top of the archive, leaving goes back to its directory.
+                None => {
+                    let archive = view.path.file_name().unwrap_or_default().to_os_string();
+                    self.pane.archive = None;
+                    archive
+                },
+            };
+            self.update_current_dir_contents()?;
+            self.select_name(&child, None);
+            return Ok(());
+        }
+        if self.pane.current_subpath.as_os_str().is_empty() && !self.in_overview() {
+            if let Some(root) = self.pane.free_root.as_mut() {
+                // `/` has no name and nothing above it.
+                let Some(child) = root.file_name().map(OsStr::to_os_string) else {
+                    return Ok(());
+                };
+                root.pop();
+                self.update_current_dir_contents()?;
+                self.select_name(&child, None);
+                self.record_navigation();
+                return Ok(());
             }
         }
-        
-        let mut app = App {
-            tabs,
-            current_tab: 0,
-            show_content: true,  // Set to true by default
-            show_editor_selection: false,
-            selected_editor: 0,
-            current_dir_contents: Vec::new(),
-            selected_item: None,
-            show_confirmation: false,
+        let Some(child) = self.pane.current_subpath.file_name().map(OsStr::to_os_string) else {
+            return Ok(());
         };
-        
-        // Initialize directory contents
-        app.update_current_dir_contents()?;
-        
-        Ok(app)
+        self.pane.current_subpath.pop();
+        self.update_current_dir_contents()?;
+        self.select_name(&child, None);
+        self.record_navigation();
+        Ok(())
     }
 
-    fn update_current_dir_contents(&mut self) -> io::Result<()> {
-        if self.tabs.is_empty() {
+    /// Goes straight back to the root of the tab, or of the free root,
+    /// from however deep the listing is.
+    fn go_to_root(&mut self) -> io::Result<()> {
+        if self.pane.current_subpath.as_os_str().is_empty() && self.pane.archive.is_none() {
             return Ok(());
         }
+        self.pane.archive = None;
+        self.pane.current_subpath = PathBuf::new();
+        self.pane.selected_item = None;
+        self.update_current_dir_contents()?;
+        self.record_navigation();
+        Ok(())
+    }
 
-        let base_path = Path::new("~/Documents/rakesh/projects").expand_home()?;
-        let current_dir = base_path.join(&self.tabs[self.current_tab]);
-        let mut contents = Vec::new();
+    /// Lists the archive called `name` in the current directory as if it
+    /// were a directory. Backspace at its top comes back out.
+    fn open_archive(&mut self, name: &OsStr, format: ArchiveFormat) {
+        let path = self.current_dir().join(name);
+        match archive::list(&path, format) {
+            Ok(members) => {
+                self.pane.archive = Some(ArchiveView { path, format, members, inner: PathBuf::new() });
+                self.pane.selected_item = None;
+                if let Err(e) = self.update_current_dir_contents() {
+                    self.status_message = Some(i18n::trf("cant_read_archive", &[("name", &name.to_string_lossy()), ("error", &e)]));
+                }
+            },
+            Err(e) => {
+                self.status_message = Some(i18n::trf("cant_read_archive", &[("name", &name.to_string_lossy()), ("error", &e)]));
+            },
+        }
+    }
 
-        for entry in fs::read_dir(current_dir)? {
-            if let Ok(entry) = entry {
-                let file_type = entry.file_type()?;
-                contents.push(DirEntry {
-                    name: entry.file_name().to_string_lossy().into_owned(),
-                    is_dir: file_type.is_dir(),
-                });
-            }
+    /// Makes the directory holding the selected entry the root of
+    /// navigation, as if reached with `g`, so `~` comes back to it. The
+    /// entry stays selected. `A` saves the directory as a tab instead.
+    fn root_here(&mut self) -> io::Result<()> {
+        if self.pane.current_subpath.as_os_str().is_empty() {
+            return Ok(());
         }
+        let selected = self.selected_name();
+        self.remember_tab_subpath();
+        self.pane.free_root = Some(self.current_dir());
+        self.pane.current_subpath = PathBuf::new();
+        self.update_current_dir_contents()?;
+        if let Some(name) = selected {
+            self.select_name(&name, None);
+        }
+        self.record_navigation();
+        Ok(())
+    }
 
-        contents.sort_by(|a, b| {
-            match (a.is_dir, b.is_dir) {
-                (true, false) => std::cmp::Ordering::Less,
-                (false, true) => std::cmp::Ordering::Greater,
-                _ => a.name.cmp(&b.name),
-            }
-        });
+    fn history_back(&mut self) -> io::Result<()> {
+        match self.pane.history.back() {
+            Some(state) => self.restore_nav_state(state),
+            None => Ok(()),
+        }
+    }
 
-        self.current_dir_contents = contents;
-        Ok(())
+    fn history_forward(&mut self) -> io::Result<()> {
+        match self.pane.history.forward() {
+            Some(state) => self.restore_nav_state(state),
+            None => Ok(()),
+        }
     }
 
-    fn switch_tab(&mut self, tab_index: usize) -> io::Result<()> {
-        if tab_index < self.tabs.len() {
-            self.current_tab = tab_index;
-            self.selected_item = None;
-            self.update_current_dir_contents()?;
+    /// Restores a history entry without recording a new one. Falls back to
+    /// the tab root if the directory has since disappeared, and to the
+    /// overview, with a message, if the root has too.
+    fn restore_nav_state(&mut self, state: NavState) -> io::Result<()> {
+        self.remember_tab_subpath();
+        match state.root {
+            NavRoot::Dir(root) => self.pane.free_root = Some(root),
+            NavRoot::Tab(tab) => {
+                let Some(tab_index) = self.tabs.iter().position(|t| *t == tab) else {
+                    return Ok(());
+                };
+                self.pane.free_root = None;
+                self.pane.current_tab = tab_index;
+            },
+        }
+        self.pane.current_subpath = state.subpath;
+        if !self.current_dir().is_dir() {
+            self.pane.current_subpath = PathBuf::new();
+        }
+        self.pane.selected_item = None;
+        if let Err(e) = self.update_current_dir_contents() {
+            self.fall_back_to_overview(e);
         }
         Ok(())
     }
@@ -115,9 +2888,9 @@ trait PathExt {
 impl PathExt for Path {
     fn expand_home(&self) -> io::Result<std::path::PathBuf> {
         if let Some(path_str) = self.to_str() {
-            if path_str.starts_with("~/") {
+            if let Some(rest) = path_str.strip_prefix("~/") {
                 if let Some(home) = dirs::home_dir() {
-                    return Ok(home.join(&path_str[2..]));
+                    return Ok(home.join(rest));
                 }
             }
         }
@@ -126,220 +2899,866 @@ impl PathExt for Path {
 }
 
 fn main() -> io::Result<()> {
+    if let Err(e) = logging::init() {
+        eprintln!("Couldn't start logging: {}", e);
+    }
+    // Load config before touching the terminal so a bad file reports cleanly.
+    let config = Config::load()?;
+    let first_run = !Config::path()?.exists();
+
     enable_raw_mode()?;
     stdout().execute(EnterAlternateScreen)?;
+    stdout().execute(EnableFocusChange)?;
     
     let backend = CrosstermBackend::new(stdout());
     let mut terminal = Terminal::new(backend)?;
-    let mut app = App::new()?;
+    let config = if first_run {
+        match setup::run(&mut terminal) {
+            Ok(config) => config,
+            Err(e) => {
+                leave_terminal()?;
+                return Err(e);
+            },
+        }
+    } else {
+        config
+    };
+    let theme = Theme::for_terminal(config.theme);
+    let error_block = block(&config);
+    let mut app = match App::new(config) {
+        Ok(app) => app,
+        Err(e) => {
+            log::error!("couldn't start: {}", e);
+            show_startup_error(&mut terminal, &theme, error_block, &e)?;
+            leave_terminal()?;
+            return Err(e);
+        },
+    };
+    app.run_startup_actions();
     
+    
+    let result = run(&mut app, &mut terminal);
+    leave_terminal()?;
+    // Undo doesn't outlive the session, so neither does what overwrites
+    // put aside for it.
+    app.undo_stack.iter().flatten().for_each(Operation::forget);
+    if let Err(e) = app.session().save() {
+        eprintln!("Couldn't save session: {}", e);
+    }
+    if app.visits_changed {
+        if let Err(e) = app.visits.save() {
+            eprintln!("Couldn't save visits: {}", e);
+        }
+    }
+    result
+}
+
+/// The main loop, until the user quits. An error ends it early, but the
+/// terminal is handed back either way by `main`.
+fn run(app: &mut App, terminal: &mut launch::Tui) -> io::Result<()> {
+    // Status messages are where most errors surface, so the log gets them all.
+    let mut logged_status = None;
     loop {
+        if app.quit {
+            break;
+        }
+        if log::log_enabled!(log::Level::Info) && app.status_message != logged_status {
+            if let Some(message) = &app.status_message {
+                log::info!("status: {}", message);
+            }
+            logged_status = app.status_message.clone();
+        }
+        app.update_preview();
+        app.image_area.set(None);
         terminal.draw(|f| {
             let size = f.size();
             
+            // Bars are one row of text, plus two of border when there are any.
+            let bar = if app.config.border_style.is_none() { 1 } else { 3 };
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
-                    Constraint::Length(3),
-                    Constraint::Length(3),
-                    Constraint::Length(3),
+                    Constraint::Length(bar),
+                    Constraint::Length(if app.side_tabs { 0 } else { bar }),
+                    Constraint::Length(bar),
                     Constraint::Min(0),
+                    Constraint::Length(1),
                 ].as_ref())
                 .split(size);
             
             // Top bar layout
+            let now = Local::now();
+            let date = app.clock.date(&now);
+            let name = "Rakesh";
+            let time = app.clock.time(&now);
+            let split = app.config.header_split.unwrap_or(config::DEFAULT_HEADER_SPLIT);
             let top_chunks = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Percentage(33),
-                    Constraint::Percentage(34),
-                    Constraint::Percentage(33),
-                ].as_ref())
+                .constraints(header_constraints(size.width, [&date, name, &time], split))
                 .split(chunks[0]);
             
             // Date
-            let date = Local::now().format("%Y-%m-%d").to_string();
             f.render_widget(
                 Paragraph::new(date)
-                    .block(Block::default().borders(Borders::ALL)),
+                    .block(block(&app.config)),
                 top_chunks[0],
             );
             
             // Name
-            f.render_widget(
-                Paragraph::new("Rakesh")
-                    .block(Block::default().borders(Borders::ALL)),
-                top_chunks[1],
-            );
+            if top_chunks[1].width > 0 {
+                f.render_widget(
+                    Paragraph::new(name)
+                        .block(block(&app.config)),
+                    top_chunks[1],
+                );
+            }
             
             // Time
-            let time = Local::now().format("%H:%M:%S").to_string();
             f.render_widget(
                 Paragraph::new(time)
-                    .block(Block::default().borders(Borders::ALL)),
+                    .block(block(&app.config)),
                 top_chunks[2],
             );
             
             // Tabs
-            let tab_titles: Vec<String> = app.tabs
+            let mut tab_titles: Vec<String> = app.tabs
                 .iter()
                 .enumerate()
-                .map(|(i, name)| format!("{}_{}", i + 1, name))
+                .map(|(i, name)| {
+                    // Tabs past the last key are labelled with their number for '.
+                    let key = app.tab_key(i).map_or((i + 1).to_string(), String::from);
+                    let label = match app.manifests.get(name) {
+                        Some(info) if info.name != *name => format!("{} ({})", name, info.name),
+                        _ => name.clone(),
+                    };
+                    let icon = app.config.tab_icons.get(name).map(String::as_str);
+                    tab_title(&key, icon, &label, app.config.tab_title_max)
+                })
                 .collect();
+            let mut selected_tab = if app.pane.free_root.is_some() { None } else { Some(app.pane.current_tab) };
+            if app.config.projects_tab {
+                tab_titles.insert(0, tab_title("0", None, &i18n::tr("all_projects"), app.config.tab_title_max));
+                selected_tab = match selected_tab {
+                    Some(i) => Some(i + 1),
+                    None if app.in_overview() => Some(0),
+                    None => None,
+                };
+            }
             
-            let tabs = Tabs::new(tab_titles)
-                .block(Block::default().borders(Borders::ALL))
-                .select(app.current_tab)
-                .style(Style::default().fg(Color::White))
-                .highlight_style(Style::default().fg(Color::Yellow));
-            
-            f.render_widget(tabs, chunks[1]);
+            let highlight = match app.config.tab_highlight {
+                TabHighlight::Text => app.theme.active_tab,
+                TabHighlight::Background => app.theme.active_tab_filled,
+            };
+            // Down the side, the tabs take a column off the content area.
+            let mut content_area = chunks[3];
+            if app.side_tabs {
+                let widest = tab_titles.iter().map(|t| width::width(t)).max().unwrap_or(0);
+                let sidebar_width = (widest as u16 + 2).min(content_area.width / 3);
+                let side_chunks = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints([Constraint::Length(sidebar_width), Constraint::Min(0)])
+                    .split(content_area);
+                content_area = side_chunks[1];
+                let items: Vec<ListItem> = tab_titles.into_iter().map(ListItem::new).collect();
+                let sidebar_block = if app.sidebar_focused { focused_block(app) } else { block(&app.config) };
+                // Stateful so the list scrolls to keep the current tab visible.
+                let mut state = ListState::default().with_selected(selected_tab);
+                let list = List::new(items)
+                    .block(sidebar_block)
+                    .style(app.theme.tab)
+                    .highlight_style(highlight);
+                f.render_stateful_widget(list, side_chunks[0], &mut state);
+            } else {
+                let mut tabs = Tabs::new(tab_titles)
+                    .block(block(&app.config))
+                    .select(selected_tab.unwrap_or(usize::MAX))
+                    .style(app.theme.tab)
+                    .highlight_style(highlight);
+                if let Some(divider) = &app.config.tab_divider {
+                    tabs = tabs.divider(divider.as_str());
+                }
+                f.render_widget(tabs, chunks[1]);
+            }
 
             // Keyboard shortcuts
-            let shortcuts = vec![
-                Span::styled("1-9", Style::default().fg(Color::Yellow)),
-                Span::raw(": Switch Tabs | "),
-                Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
-                Span::raw(": Navigate | "),
-                Span::styled("Enter", Style::default().fg(Color::Yellow)),
-                Span::raw(": Select | "),
-                Span::styled("y/n", Style::default().fg(Color::Yellow)),
-                Span::raw(": Confirm | "),
-                Span::styled("q", Style::default().fg(Color::Yellow)),
-                Span::raw(": Quit"),
+            let tab_keys = tab_keys_hint(app.tab_keys());
+            let hints = [
+                (tab_keys.as_str(), "key_switch_tabs"),
+                ("'", "key_tab_by_number"),
+                ("0", "key_all_projects"),
+                ("H", "key_home"),
+                ("[/]", "key_cycle_tab"),
+                ("t", "key_tab_switcher"),
+                ("b", "key_side_tabs"),
+                ("T", "key_find_tab"),
+                ("Ctrl+F", "key_find_file"),
+                ("r", "key_recent"),
+                ("A", "key_add_tab"),
+                ("N", "key_note"),
+                ("c", "key_listing"),
+                ("↑/↓", "key_navigate"),
+                ("Enter", "key_select"),
+                ("Backspace", "key_up"),
+                ("~/Home", "key_root"),
+                ("F", "key_root_here"),
+                ("Alt+←/→", "key_history"),
+                ("Ctrl+←/→", "key_move_tab"),
+                ("y/n", "key_confirm"),
+                (":", "key_command"),
+                ("R", "key_task"),
+                ("g", "key_go_to"),
+                ("P", "key_path_display"),
+                ("h/U", "key_hide_tab"),
+                ("/", "key_filter"),
+                ("s/S", "key_sort"),
+                (".", "key_dotfiles"),
+                ("p", "key_pin"),
+                ("o", "key_open_with"),
+                ("e", "key_open_dir"),
+                ("d", "key_diff_tool"),
+                ("f", "key_format"),
+                ("u", "key_disk_usage"),
+                ("z/x", "key_archive"),
+                ("|/Tab", "key_split"),
+                ("Space/Shift+↑↓", "key_mark"),
+                ("C/M", "key_transfer"),
+                ("Y", "key_copy_to_tab"),
+                ("X", "key_export"),
+                ("Ctrl+Z", "key_undo"),
+                ("v/O/D", "key_preview"),
+                ("PgUp/PgDn", "key_scroll_preview"),
+                (",", "key_config"),
+                ("q", "key_quit"),
             ];
+            let mut shortcuts = Vec::new();
+            for (i, (key, label)) in hints.into_iter().enumerate() {
+                if i > 0 {
+                    shortcuts.push(Span::raw(" | "));
+                }
+                shortcuts.push(Span::styled(key, app.theme.accent));
+                shortcuts.push(Span::raw(format!(": {}", i18n::tr(label))));
+            }
         
-            f.render_widget(
-                Paragraph::new(Line::from(shortcuts))
-                    .block(Block::default().borders(Borders::ALL))
-                    .style(Style::default().fg(Color::White)),
-                chunks[2],
-            );
+            if let Some(input) = &app.input {
+                let line = Line::from(vec![
+                    Span::styled(input.prompt.label(app.search_case_sensitive), app.theme.accent),
+                    Span::raw(input.text.as_str()),
+                    Span::styled("█", app.theme.text),
+                ]);
+                f.render_widget(
+                    Paragraph::new(line)
+                        .block(block(&app.config))
+                        .style(app.theme.text),
+                    chunks[2],
+                );
+            } else {
+                f.render_widget(
+                    Paragraph::new(Line::from(shortcuts))
+                        .block(block(&app.config))
+                        .style(app.theme.text),
+                    chunks[2],
+                );
+            }
             
-            // Content area
-            if app.show_content {
-                let items: Vec<ListItem> = app.current_dir_contents
+            // Content area: the list, the second pane in dual-pane mode and
+            // the preview, side by side.
+            let columns = 1 + app.other_pane.is_some() as u32 + app.show_preview as u32;
+            let areas = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(vec![Constraint::Ratio(1, columns); columns as usize])
+                .split(content_area);
+            let preview_area = app.show_preview.then(|| areas[areas.len() - 1]);
+            // Only worth marking focus when there's more than one column.
+            let list_focused = (columns > 1 || app.side_tabs) && !app.preview_focused && !app.sidebar_focused;
+            if app.has_no_projects() {
+                let lines = vec![
+                    Line::from(""),
+                    Line::from(Span::styled(i18n::tr("no_projects"), app.theme.text)),
+                    Line::from(Span::styled(app.base_path.display().to_string(), app.theme.muted)),
+                ];
+                let panel = Paragraph::new(lines)
+                    .alignment(Alignment::Center)
+                    .block(block(&app.config));
+                f.render_widget(panel, areas[0]);
+            } else if app.show_content {
+                match &app.other_pane {
+                    Some(other) => {
+                        let (left, right) = if app.pane_on_left { (&app.pane, other) } else { (other, &app.pane) };
+                        render_listing(f, app, left, areas[0], list_focused && app.pane_on_left);
+                        render_listing(f, app, right, areas[1], list_focused && !app.pane_on_left);
+                    },
+                    None => render_listing(f, app, &app.pane, areas[0], list_focused),
+                }
+            }
+
+            if let Some(area) = preview_area {
+                render_preview(f, app, area);
+            }
+
+            // Completion candidates, just below the input bar
+            if let Some(completion) = app.input.as_ref().and_then(|i| i.completion.as_ref()) {
+                let items: Vec<ListItem> = completion.candidates
                     .iter()
                     .enumerate()
-                    .map(|(index, entry)| {
-                        let is_selected = app.selected_item == Some(index);
-                        let (icon, color) = if entry.is_dir {
-                            ("📁", Color::Cyan)
-                        } else {
-                            ("📄", Color::White)
-                        };
-                        
-                        let style = if is_selected {
-                            Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
-                        } else {
-                            Style::default().fg(color)
-                        };
-                        
-                        let content = Line::from(vec![
-                            Span::raw(icon),
-                            Span::raw(" "),
-                            Span::styled(&entry.name, style)
-                        ]);
-                        ListItem::new(content)
+                    .map(|(i, candidate)| {
+                        let style = if completion.selected == Some(i) { app.theme.selected } else { app.theme.text };
+                        ListItem::new(Span::styled(candidate.as_str(), style))
                     })
                     .collect();
+                let widest = completion.candidates.iter().map(|c| width::width(c)).max().unwrap_or(0);
+                let below = chunks[2].y + chunks[2].height;
+                let area = ratatui::layout::Rect {
+                    x: chunks[2].x,
+                    y: below,
+                    width: (widest as u16 + 2).min(chunks[2].width),
+                    height: (completion.candidates.len() as u16 + 2).min(size.height.saturating_sub(below)),
+                };
+                // Stateful so the list scrolls to keep the cycled-to candidate visible.
+                let mut state = ListState::default().with_selected(completion.selected);
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(List::new(items).block(block(&app.config)), area, &mut state);
+            }
 
-                let list = List::new(items)
-                    .block(Block::default()
-                        .title(format!(" Contents of {} ", app.tabs[app.current_tab]))
-                        .borders(Borders::ALL));
-
-                f.render_widget(list, chunks[3]);
+            // Status bar
+            let badge = format!(" {} ", app.path_display.label());
+            let message = app.status_message.as_deref();
+            let project = app.current_tab_name()
+                .and_then(|tab| app.manifests.get(tab))
+                .map(|info| Span::styled(format!(" │ {}", info.label()), app.theme.muted));
+            let dir_size = match app.disk_usage.get(&app.current_dir()) {
+                Some(Usage::Calculating) => Some(Span::styled(format!(" │ {}", i18n::tr("calculating")), app.theme.muted)),
+                Some(Usage::Done(size)) => Some(Span::styled(format!(" │ {}", usage::human_size(*size)), app.theme.muted)),
+                Some(Usage::Failed(e)) => Some(Span::styled(format!(" │ {}", i18n::trf("size_unknown", &[("error", e)])), app.theme.error)),
+                None => None,
+            };
+            let free_space = app.free_space
+                .filter(|_| app.config.show_free_space)
+                .map(|bytes| Span::styled(format!(" │ {}", i18n::trf("free_space", &[("size", &usage::human_size(bytes))])), app.theme.muted));
+            let job = app.archive_job.as_ref().map(|job| {
+                Span::styled(format!(" │ {}… {}/{}", job.label, job.done, job.total), app.theme.accent)
+            });
+            // The message wins over the path when space runs out.
+            let path = app.display_path();
+            let path_room = (chunks[4].width as usize)
+                .saturating_sub(width::width(&badge) + 1)
+                .saturating_sub(project.as_ref().map_or(0, |s| width::width(&s.content)))
+                .saturating_sub(job.as_ref().map_or(0, |s| width::width(&s.content)))
+                .saturating_sub(dir_size.as_ref().map_or(0, |s| width::width(&s.content)))
+                .saturating_sub(free_space.as_ref().map_or(0, |s| width::width(&s.content)))
+                .saturating_sub(message.map_or(0, |m| width::width(" │ ") + width::width(m)));
+            let mut spans = vec![
+                Span::styled(badge, app.theme.badge),
+                Span::raw(" "),
+                Span::raw(width::truncate_start(&path, path_room)),
+            ];
+            spans.extend(job);
+            spans.extend(project);
+            spans.extend(dir_size);
+            spans.extend(free_space);
+            if let Some(message) = message {
+                spans.push(Span::raw(" │ "));
+                spans.push(Span::styled(message, app.theme.accent));
             }
+            let status = Line::from(spans);
+            f.render_widget(Paragraph::new(status), chunks[4]);
+
+            // Popups stay clear of the header and tab bar unless configured
+            // to centre on the whole screen.
+            let popup_bounds = match app.config.popup_area {
+                PopupArea::Content => chunks[3],
+                PopupArea::Screen => size,
+            };
 
             // Add confirmation popup if needed
-            if app.show_confirmation {
-                let popup = Paragraph::new("Open in Neovim? (y/n)")
-                    .block(Block::default()
-                        .borders(Borders::ALL)
-                        .style(Style::default().fg(Color::Yellow)));
+            if let Some(confirmation) = &app.confirmation {
+                let message = confirmation.message(&app.config);
+                // One line if it fits; otherwise as wide as the screen allows,
+                // with a spare row since wrapping at words wastes some width.
+                let width = width::width(&message) as u16;
+                let inner = width.min(popup_bounds.width.saturating_sub(4)).max(1);
+                let rows = width.div_ceil(inner) + u16::from(width > inner);
+                let popup = Paragraph::new(message)
+                    .wrap(Wrap { trim: true })
+                    .block(block(&app.config)
+                        .style(app.theme.accent));
                 
-                let area = centered_rect(30, 20, size);
+                let area = fitted_rect(inner, rows, popup_bounds);
+                f.render_widget(Clear, area);
                 f.render_widget(popup, area);
             }
             
             // Editor selection popup
             if app.show_editor_selection {
-                let popup_block = Block::default()
-                    .borders(Borders::ALL)
-                    .title("Select Editor");
+                let title = i18n::tr("select_editor");
+                let popup_block = block(&app.config)
+                    .title(title.clone());
                 
-                let editors = vec!["VSCode", "Neovim"];
-                let editor_text = editors.join("\n");
-                let popup = Paragraph::new(editor_text)
+                let editors = ["VSCode", "Neovim"];
+                let editor_lines: Vec<Line> = editors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        if i == app.selected_editor {
+                            Line::from(Span::styled(*name, app.theme.accent))
+                        } else {
+                            Line::from(*name)
+                        }
+                    })
+                    .collect();
+                let width = editor_lines.iter().map(Line::width).max().unwrap_or(0).max(width::width(&title));
+                let area = fitted_rect(width as u16, editor_lines.len() as u16, popup_bounds);
+                let popup = Paragraph::new(editor_lines)
                     .block(popup_block)
                     .style(Style::default());
                 
-                let area = centered_rect(30, 20, size);
                 f.render_widget(popup, area);
             }
+
+            // Welcome panel
+            if app.show_welcome {
+                let key = |k: &'static str, what: &'static str| Line::from(vec![
+                    Span::styled(format!("{:>6}  ", k), app.theme.accent),
+                    Span::styled(i18n::tr(what), app.theme.text),
+                ]);
+                let lines = vec![
+                    Line::from(Span::styled(i18n::tr("welcome"), app.theme.accent)),
+                    Line::from(Span::styled(i18n::trf("welcome_projects", &[("count", &app.tabs.len()), ("path", &app.base_path.display())]), app.theme.muted)),
+                    Line::from(""),
+                    Line::from(vec![
+                        Span::styled(format!("{:>6}  ", tab_keys_hint(app.tab_keys())), app.theme.accent),
+                        Span::styled(i18n::tr("welcome_switch_project"), app.theme.text),
+                    ]),
+                    key("'", "welcome_tab_number"),
+                    key("t", "welcome_switch_letter"),
+                    key("Enter", "welcome_open"),
+                    key("c", "welcome_listing"),
+                    key(",", "welcome_config"),
+                    Line::from(""),
+                    Line::from(Span::styled(i18n::tr("welcome_start"), app.theme.muted)),
+                ];
+                let width = lines.iter().map(Line::width).max().unwrap_or(0);
+                let area = fitted_rect(width as u16, lines.len() as u16, popup_bounds);
+                let popup = Paragraph::new(lines)
+                    .wrap(Wrap { trim: false })
+                    .block(block(&app.config));
+
+                f.render_widget(Clear, area);
+                f.render_widget(popup, area);
+            }
+
+            // Hidden tabs popup
+            if let Some(selected) = app.hidden_tabs_popup {
+                let items: Vec<ListItem> = if app.config.hidden_tabs.is_empty() {
+                    vec![ListItem::new(Span::styled(i18n::tr("no_hidden_tabs"), app.theme.muted))]
+                } else {
+                    app.config.hidden_tabs
+                        .iter()
+                        .enumerate()
+                        .map(|(i, name)| {
+                            let style = if i == selected {
+                                app.theme.selected
+                            } else {
+                                Style::default()
+                            };
+                            ListItem::new(Span::styled(name.as_str(), style))
+                        })
+                        .collect()
+                };
+                let popup = List::new(items)
+                    .block(block(&app.config).title(format!(" {} ", i18n::tr("hidden_tabs"))));
+
+                let area = centered_rect(40, 40, popup_bounds);
+                f.render_widget(Clear, area);
+                f.render_widget(popup, area);
+            }
+
+            // Recent files overlay
+            if let Some(view) = &app.recent {
+                let items: Vec<ListItem> = if view.files.is_empty() {
+                    vec![ListItem::new(Span::styled(i18n::tr("no_recent_files"), app.theme.muted))]
+                } else {
+                    view.files
+                        .iter()
+                        .enumerate()
+                        .map(|(i, file)| {
+                            let style = if i == view.selected { app.theme.selected } else { app.theme.text };
+                            let modified = chrono::DateTime::<Local>::from(file.modified).format("%Y-%m-%d %H:%M");
+                            ListItem::new(Line::from(vec![
+                                Span::styled(format!("{}  ", modified), app.theme.muted),
+                                Span::styled(Path::new(&file.tab).join(&file.path).display().to_string(), style),
+                            ]))
+                        })
+                        .collect()
+                };
+                let popup = List::new(items)
+                    .block(block(&app.config).title(format!(" {} ", i18n::tr("recent_files"))));
+
+                let area = centered_rect(70, 70, popup_bounds);
+                let mut state = ListState::default().with_selected(Some(view.selected));
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(popup, area, &mut state);
+            }
+
+            // Tab switcher overlay
+            if let Some(pick) = app.tab_switcher {
+                let items: Vec<ListItem> = app.tabs
+                    .iter()
+                    .zip(tab_hotkeys(&app.tabs))
+                    .map(|(name, key)| {
+                        let key = key.map_or("   ".to_string(), |k| format!("[{}]", k));
+                        ListItem::new(Line::from(vec![
+                            Span::styled(key, app.theme.accent),
+                            Span::raw(" "),
+                            Span::styled(name.as_str(), app.theme.text),
+                        ]))
+                    })
+                    .collect();
+                let popup = List::new(items)
+                    .block(block(&app.config).title(format!(" {} ", i18n::tr(match pick {
+                        TabPick::Switch => "switch_tab",
+                        TabPick::CopyTo => "copy_to_tab",
+                    }))));
+
+                let area = centered_rect(40, 60, popup_bounds);
+                f.render_widget(Clear, area);
+                f.render_widget(popup, area);
+            }
+
+            // Tab search overlay
+            if let Some(search) = &app.tab_search {
+                let items: Vec<ListItem> = app.tab_search_matches(&search.query)
+                    .into_iter()
+                    .enumerate()
+                    .map(|(row, index)| {
+                        let style = if row == search.selected { app.theme.selected } else { app.theme.text };
+                        ListItem::new(Span::styled(app.tabs[index].as_str(), style))
+                    })
+                    .collect();
+                let title = Line::from(vec![
+                    Span::raw(format!(" {}", i18n::tr("find_tab"))),
+                    Span::styled(search.query.as_str(), app.theme.accent),
+                    Span::styled("█ ", app.theme.text),
+                ]);
+                let mut state = ListState::default().with_selected(Some(search.selected));
+                let popup = List::new(items).block(block(&app.config).title(title));
+
+                let area = centered_rect(40, 60, popup_bounds);
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(popup, area, &mut state);
+            }
+
+            // File finder overlay
+            if let Some(finder) = &app.finder {
+                let files = app.finder_files.get(&finder.tab).map_or(&[][..], Vec::as_slice);
+                let items: Vec<ListItem> = finder.matches
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(row, &i)| {
+                        let style = if row == finder.selected { app.theme.selected } else { app.theme.text };
+                        files.get(i).map(|file| ListItem::new(Span::styled(file.display().to_string(), style)))
+                    })
+                    .collect();
+                let title = Line::from(vec![
+                    Span::raw(format!(" {}", i18n::trf("find_file", &[("tab", &finder.tab)]))),
+                    Span::styled(finder.query.as_str(), app.theme.accent),
+                    Span::styled("█ ", app.theme.text),
+                ]);
+                let mut state = ListState::default().with_selected(Some(finder.selected));
+                let popup = List::new(items).block(block(&app.config).title(title));
+
+                let area = centered_rect(70, 70, popup_bounds);
+                f.render_widget(Clear, area);
+                f.render_stateful_widget(popup, area, &mut state);
+            }
+
+            // Project note popup
+            if let Some(note) = &app.note_popup {
+                let text = if note.trim().is_empty() {
+                    Paragraph::new(Span::styled(i18n::tr("no_note"), app.theme.muted))
+                } else {
+                    Paragraph::new(note.as_str()).style(app.theme.text)
+                };
+                let title = format!(" {} ", i18n::trf("note", &[("name", &app.current_tab_name().map_or("", String::as_str))]));
+                let popup = text
+                    .block(block(&app.config).title(title))
+                    .wrap(Wrap { trim: false });
+
+                let area = centered_rect(60, 60, popup_bounds);
+                f.render_widget(Clear, area);
+                f.render_widget(popup, area);
+            }
+
+            // Shell command output overlay
+            if let Some(output) = &app.command_output {
+                let status = match &output.status {
+                    CommandStatus::Running => Span::styled(i18n::tr("running"), app.theme.accent),
+                    CommandStatus::Exited(Some(0)) => Span::styled(i18n::trf("exit_code", &[("code", &0)]), app.theme.success),
+                    CommandStatus::Exited(Some(code)) => Span::styled(i18n::trf("exit_code", &[("code", code)]), app.theme.error),
+                    CommandStatus::Exited(None) => Span::styled(i18n::tr("killed"), app.theme.error),
+                    CommandStatus::Failed(e) => Span::styled(i18n::trf("failed", &[("error", e)]), app.theme.error),
+                };
+                let title = Line::from(vec![
+                    Span::raw(format!(" {} — ", output.command)),
+                    status,
+                    Span::raw(" "),
+                ]);
+                let lines: Vec<Line> = output.lines
+                    .iter()
+                    .map(|line| {
+                        let style = if line.stderr { app.theme.error } else { app.theme.text };
+                        Line::from(Span::styled(line.text.as_str(), style))
+                    })
+                    .collect();
+                let area = centered_rect(80, 70, popup_bounds);
+                let output_block = block(&app.config).title(title);
+                output.view_height.set(output_block.inner(area).height);
+                let popup = Paragraph::new(lines)
+                    .block(output_block)
+                    .scroll((output.scroll, 0));
+
+                f.render_widget(Clear, area);
+                f.render_widget(popup, area);
+            }
+
+            if app.is_idle() {
+                f.buffer_mut().set_style(size, Style::default().add_modifier(Modifier::DIM));
+            }
         })?;
+        app.sync_image(terminal)?;
         
     // ******************************** start ***********************************************
         
+        if let Some(output) = app.command_output.as_mut() {
+            output.poll();
+        }
+        app.disk_usage.poll();
+        app.poll_archive_job()?;
+        app.poll_startup_command();
+        app.poll_actions()?;
+        app.auto_refresh();
+        app.autosave_session();
+        app.update_free_space();
+        app.expire_confirmation();
+        app.poll_config_watcher()?;
+
         if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
+            let event = event::read()?;
+            let was_idle = app.is_idle();
+            app.last_input = Instant::now();
+            if was_idle && matches!(event, Event::Key(_)) {
+                // The key that wakes the screen isn't also acted on.
+                continue;
+            }
+            if let Event::FocusGained = event {
+                app.handle_focus_gained()?;
+            }
+            if let Event::Key(key) = event {
+                log::debug!("key {:?} {:?}", key.code, key.modifiers);
+                if app.show_welcome {
+                    app.show_welcome = false;
+                    continue;
+                }
+                if app.input.is_some() {
+                    app.handle_input_key(key)?;
+                    continue;
+                }
+                if app.command_output.is_some() {
+                    app.handle_output_key(key);
+                    continue;
+                }
+                if app.hidden_tabs_popup.is_some() {
+                    app.handle_hidden_tabs_key(key)?;
+                    continue;
+                }
+                if app.note_popup.is_some() {
+                    app.handle_note_key(key, terminal);
+                    continue;
+                }
+                if app.recent.is_some() {
+                    app.handle_recent_key(key)?;
+                    continue;
+                }
+                if app.tab_switcher.is_some() {
+                    app.handle_tab_switcher_key(key)?;
+                    continue;
+                }
+                if app.tab_search.is_some() {
+                    app.handle_tab_search_key(key)?;
+                    continue;
+                }
+                if app.finder.is_some() {
+                    app.handle_finder_key(key)?;
+                    continue;
+                }
+                if app.confirmation.is_some() {
+                    app.handle_confirmation_key(key, terminal)?;
+                    continue;
+                }
+                app.status_message = None;
                 match key.code {
+                    // q and Esc close whatever popup is up before q quits.
+                    KeyCode::Esc | KeyCode::Char('q') if app.show_editor_selection => app.show_editor_selection = false,
                     KeyCode::Char('q') => break,
+                    // Nothing inside an archive is on disk to act on.
+                    KeyCode::Char('p' | 'o' | 'e' | 'd' | 'f' | 'F' | 'u' | 'z' | 'x' | 'C' | 'M' | 'Y' | 'A' | ' ')
+                        if app.pane.archive.is_some() && !key.modifiers.contains(KeyModifiers::CONTROL) =>
+                    {
+                        app.status_message = Some(i18n::tr("archive_read_only"));
+                    },
+                    KeyCode::Char(':') => app.open_input(Prompt::Command),
+                    KeyCode::Char('P') => app.path_display = app.path_display.next(),
+                    KeyCode::Char('h') => app.hide_current_tab()?,
+                    KeyCode::Char('/') => app.open_input(Prompt::Filter),
+                    KeyCode::Char('.') => {
+                        app.show_hidden = !app.show_hidden;
+                        app.apply_view();
+                    },
+                    KeyCode::Char('s') => {
+                        app.sort_mode = app.sort_mode.next();
+                        app.apply_view();
+                    },
+                    KeyCode::Char('S') => {
+                        app.sort_reverse = !app.sort_reverse;
+                        app.apply_view();
+                    },
+                    KeyCode::Char('p') => app.toggle_pin(),
+                    KeyCode::Char('o') => app.open_selected_with_default_app(),
+                    KeyCode::Char('e') => app.open_dir_in_editor(terminal),
+                    KeyCode::Char('d') => app.diff_selected(terminal)?,
+                    KeyCode::Char('u') => app.disk_usage.request(&app.current_dir()),
+                    KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => app.undo()?,
+                    KeyCode::Char('z') => app.compress_selected(),
+                    KeyCode::Char('R') => app.run_task(),
+                    KeyCode::Char('x') => app.confirm_extract(),
+                    KeyCode::Char('v') => {
+                        app.show_preview = !app.show_preview;
+                        app.preview_focused = false;
+                    },
+                    KeyCode::PageUp if app.show_preview => app.scroll_preview(false, true),
+                    KeyCode::PageDown if app.show_preview => app.scroll_preview(true, true),
+                    KeyCode::Up if app.preview_focused => app.scroll_preview(false, false),
+                    KeyCode::Down if app.preview_focused => app.scroll_preview(true, false),
+                    KeyCode::Up if app.sidebar_focused => app.cycle_tab(false)?,
+                    KeyCode::Down if app.sidebar_focused => app.cycle_tab(true)?,
+                    KeyCode::Enter if app.sidebar_focused => {
+                        app.sidebar_focused = false;
+                        app.show_content = true;
+                    },
+                    KeyCode::Char('b') => app.toggle_side_tabs(),
+                    KeyCode::Char('O') => app.outline_mode = !app.outline_mode,
+                    KeyCode::Char('D') => app.diff_mode = !app.diff_mode,
+                    // [ and ] pick outline entries while the outline is up.
+                    KeyCode::Char('[') if app.show_preview && app.outline_mode => app.move_outline_selection(false),
+                    KeyCode::Char(']') if app.show_preview && app.outline_mode => app.move_outline_selection(true),
+                    KeyCode::Char('[') => app.cycle_tab(false)?,
+                    KeyCode::Char(']') => app.cycle_tab(true)?,
+                    KeyCode::Char('U') => app.hidden_tabs_popup = Some(0),
+                    KeyCode::Char('0') => app.open_overview()?,
+                    KeyCode::Char('H') => app.go_home()?,
+                    KeyCode::Char('~') if app.show_content => app.go_to_root()?,
+                    KeyCode::Char('g') => app.open_input(Prompt::GoTo),
+                    KeyCode::Char('t') => app.tab_switcher = Some(TabPick::Switch),
+                    KeyCode::Char('Y') => app.tab_switcher = Some(TabPick::CopyTo),
+                    KeyCode::Char('T') => app.tab_search = Some(TabSearch { query: String::new(), selected: 0 }),
+                    KeyCode::Char('f') if key.modifiers.contains(KeyModifiers::CONTROL) => app.open_finder(),
+                    KeyCode::Char('N') => app.open_note(),
+                    KeyCode::Char('r') => app.open_recent(),
+                    KeyCode::Char('c') => app.show_content = !app.show_content,
+                    KeyCode::Char('|') => app.toggle_split()?,
+                    KeyCode::Tab => app.cycle_focus()?,
+                    KeyCode::Char('C') => app.transfer_selected(false)?,
+                    KeyCode::Char('M') => app.transfer_selected(true)?,
+                    KeyCode::Char(',') => app.edit_config(terminal)?,
+                    KeyCode::Char('\'') => app.open_input(Prompt::TabNumber),
+                    KeyCode::Char('A') => app.open_input(Prompt::TabLabel),
+                    KeyCode::Char('F') => app.root_here()?,
+                    KeyCode::Char('f') if !app.config.formatters.is_empty() => app.format_selected(),
+                    KeyCode::Char('X') => app.open_input(Prompt::Export),
+                    KeyCode::Char('a') if app.has_no_projects() => app.open_input(Prompt::NewProject),
+                    KeyCode::Char(' ') if app.show_content => app.toggle_mark(),
                     KeyCode::Char(c) => {
-                        // Handle number keys 1-9 for tab switching
-                        if let Some(digit) = c.to_digit(10) {
-                            if digit > 0 && digit <= 9 {
-                                app.switch_tab((digit - 1) as usize)?;
-                            }
+                        if app.pane.archive.is_none() && app.run_action(c) {
+                            continue;
+                        }
+                        if let Some(index) = app.tab_keys().chars().position(|k| k == c) {
+                            app.switch_tab_by_key(index)?;
                         }
                     },
-                    KeyCode::Up => {
-                        if app.show_content {
-                            if let Some(selected) = app.selected_item {
-                                if selected > 0 {
-                                    app.selected_item = Some(selected - 1);
-                                }
-                            } else {
-                                app.selected_item = Some(0);
+                    KeyCode::Left if key.modifiers.contains(KeyModifiers::CONTROL) => app.move_tab(false),
+                    KeyCode::Right if key.modifiers.contains(KeyModifiers::CONTROL) => app.move_tab(true),
+                    KeyCode::Left if key.modifiers.contains(KeyModifiers::ALT) => {
+                        app.history_back()?;
+                    },
+                    KeyCode::Right if key.modifiers.contains(KeyModifiers::ALT) => {
+                        app.history_forward()?;
+                    },
+                    KeyCode::Up if app.show_editor_selection => {
+                        app.selected_editor = app.selected_editor.saturating_sub(1);
+                    },
+                    KeyCode::Down if app.show_editor_selection => {
+                        app.selected_editor = (app.selected_editor + 1).min(1);
+                    },
+                    KeyCode::Enter if app.show_editor_selection => app.show_editor_selection = false,
+                    KeyCode::Up if app.show_content && key.modifiers.contains(KeyModifiers::SHIFT) => app.extend_marks(false),
+                    KeyCode::Down if app.show_content && key.modifiers.contains(KeyModifiers::SHIFT) => app.extend_marks(true),
+                    KeyCode::Up if app.show_content => {
+                        app.pane.anchor = None;
+                        if let Some(selected) = app.pane.selected_item {
+                            if selected > 0 {
+                                app.pane.selected_item = Some(selected - 1);
                             }
+                        } else if !app.pane.current_dir_contents.is_empty() {
+                            app.pane.selected_item = Some(0);
                         }
                     },
-                    KeyCode::Down => {
-                        if app.show_content {
-                            if let Some(selected) = app.selected_item {
-                                if selected < app.current_dir_contents.len() - 1 {
-                                    app.selected_item = Some(selected + 1);
-                                }
-                            } else {
-                                app.selected_item = Some(0);
+                    KeyCode::Down if app.show_content => {
+                        app.pane.anchor = None;
+                        if let Some(selected) = app.pane.selected_item {
+                            if selected + 1 < app.pane.current_dir_contents.len() {
+                                app.pane.selected_item = Some(selected + 1);
                             }
+                        } else if !app.pane.current_dir_contents.is_empty() {
+                            app.pane.selected_item = Some(0);
                         }
                     },
-                    KeyCode::Enter => {
-                        if app.show_content && app.selected_item.is_some() {
-                            app.show_confirmation = false;
+                    KeyCode::Enter if app.show_content => {
+                        if let Some(selected) = app.pane.selected_item {
+                            let entry = app.pane.current_dir_contents[selected].clone();
+                            if entry.parent {
+                                app.leave_dir()?;
+                            } else if entry.is_dir && app.in_overview() {
+                                app.open_project(&entry.file_name)?;
+                            } else if entry.is_dir {
+                                app.enter_dir(&entry.file_name)?;
+                            } else if app.pane.archive.is_some() {
+                                app.status_message = Some(i18n::tr("archive_read_only"));
+                            } else if let Some((format, _)) = ArchiveFormat::detect(&entry.name) {
+                                app.open_archive(&entry.file_name, format);
+                            } else if entry.broken_link {
+                                app.status_message = Some(app.broken_link_message(&entry));
+                            } else {
+                                let path = app.current_dir().join(&entry.file_name);
+                                app.ask(Confirmation::Open(entry.name.clone(), path));
+                            }
+                        } else {
+                            match app.config.enter_without_selection {
+                                EnterWithoutSelection::SelectFirst if !app.pane.current_dir_contents.is_empty() => {
+                                    app.pane.selected_item = Some(0);
+                                },
+                                EnterWithoutSelection::OpenDir => app.open_dir_in_editor(terminal),
+                                _ => {},
+                            }
                         }
                     },
-                    KeyCode::Char('y') if app.show_confirmation => {
-                        if let Some(selected) = app.selected_item {
-                            let entry = &app.current_dir_contents[selected];
-                            let path = Path::new("~/Documents/rakesh/projects")
-                                .expand_home()?
-                                .join(&app.tabs[app.current_tab])
-                                .join(&entry.name);
-                            
-                            Command::new("alacritty")
-                                .args(&["-e", "nvim"])
-                                .arg(path)
-                                .spawn()?;
-                        }
-                        app.show_confirmation = false;
+                    KeyCode::Backspace if app.show_content => {
+                        app.leave_dir()?;
                     },
-                    KeyCode::Char('n') if app.show_confirmation => {
-                        app.show_confirmation = false;
+                    KeyCode::Home if app.show_content => app.go_to_root()?,
+                    KeyCode::Esc if !app.pane.marked.is_empty() => {
+                        app.pane.marked.clear();
+                        app.pane.anchor = None;
                     },
-                    KeyCode::Esc => app.show_editor_selection = false,
+                    KeyCode::Esc if app.filter.is_some() => app.set_filter(""),
                     _ => {},
                 }
             }
@@ -352,12 +3771,368 @@ fn main() -> io::Result<()> {
     // ******************************** END ***********************************************
 
     }
-    
+    Ok(())
+}
+
+/// Hands the terminal back to the shell the way it was found.
+fn leave_terminal() -> io::Result<()> {
+    stdout().execute(DisableFocusChange)?;
     disable_raw_mode()?;
     stdout().execute(LeaveAlternateScreen)?;
     Ok(())
 }
 
+/// Shows why bod couldn't start until a key is pressed, rather than
+/// dropping straight back to the shell with a bare io error.
+fn show_startup_error<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    theme: &Theme,
+    border: Block<'static>,
+    error: &io::Error,
+) -> io::Result<()> {
+    terminal.draw(|f| {
+        let area = centered_rect(60, 40, f.size());
+        let text = vec![
+            Line::from(Span::styled(error.to_string(), theme.error)),
+            Line::from(""),
+            Line::from(Span::styled(i18n::tr("projects_dir_help"), theme.muted)),
+            Line::from(""),
+            Line::from(i18n::tr("press_key_to_quit")),
+        ];
+        let panel = Paragraph::new(text)
+            .wrap(Wrap { trim: false })
+            .block(border.title(format!(" {} ", i18n::tr("cant_start"))));
+        f.render_widget(Clear, area);
+        f.render_widget(panel, area);
+    })?;
+    loop {
+        if let Event::Key(_) = event::read()? {
+            return Ok(());
+        }
+    }
+}
+
+/// How far the selected name has scrolled when `overflow` columns don't fit.
+fn marquee_offset(app: &App, overflow: usize) -> usize {
+    // It waits at each end, then scrolls back and forth. Any key starts it
+    // over, including the one that moved the selection.
+    const STEP: Duration = Duration::from_millis(200);
+    // Steps spent waiting at each end.
+    const PAUSE: usize = 5;
+    let step = (app.last_input.elapsed().as_millis() / STEP.as_millis()) as usize;
+    let leg = overflow + PAUSE;
+    match step % (2 * leg) {
+        s if s < leg => s.saturating_sub(PAUSE),
+        s => overflow - (s - leg).saturating_sub(PAUSE).min(overflow),
+    }
+}
+
+/// Draws `pane`'s listing. With more than one column on screen the
+/// focused one gets an accented border.
+fn render_listing(f: &mut ratatui::Frame, app: &App, pane: &Pane, area: ratatui::layout::Rect, focused: bool) {
+    // Inside the borders, less the icon and the space after it.
+    let name_room = (area.width as usize).saturating_sub(2 + 3);
+    let dir = app.dir_of(pane);
+    let items: Vec<ListItem> = pane.current_dir_contents
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let is_selected = pane.selected_item == Some(index);
+            let (icon, type_style) = if entry.parent {
+                ("🔼", app.theme.muted)
+            } else if entry.broken_link {
+                ("🔗", app.theme.broken_link)
+            } else if entry.is_dir {
+                ("📁", app.theme.directory)
+            } else {
+                ("📄", app.theme.file)
+            };
+
+            let is_marked = !pane.marked.is_empty() && pane.marked.contains(&dir.join(&entry.file_name));
+            // Each layer patches the one below, so a modifier from the type
+            // or a colour rule survives being marked or selected.
+            let base = match app.color_rules.style_for(&entry.name) {
+                Some(rule) => type_style.patch(rule),
+                None => type_style,
+            };
+            let style = match (is_selected, is_marked) {
+                (true, true) => base.patch(app.theme.selected_marked),
+                (true, false) => base.patch(app.theme.selected),
+                (false, true) => base.patch(app.theme.marked),
+                (false, false) => base,
+            };
+
+            let mut details = Vec::new();
+            if is_marked {
+                details.push(Span::styled(" ✓", app.theme.marked));
+            }
+            if entry.pinned {
+                details.push(Span::styled(" 📌", app.theme.accent));
+            }
+            if entry.is_dir && !entry.parent {
+                let count = match entry.child_count {
+                    Some(count) => format!(" ({})", count),
+                    None => " (?)".to_string(),
+                };
+                details.push(Span::styled(count, app.theme.muted));
+            }
+            if entry.lossy {
+                details.push(Span::styled(format!(" {}", i18n::tr("non_utf8")), app.theme.muted));
+            }
+            if entry.broken_link {
+                details.push(Span::styled(format!(" {}", i18n::tr("broken_link")), app.theme.broken_link));
+            }
+            let details_width: usize = details.iter().map(|s| width::width(&s.content)).sum();
+            let room = name_room.saturating_sub(details_width);
+            let name = if is_selected && app.config.marquee_selected && width::width(&entry.name) > room {
+                Cow::Owned(width::window(&entry.name, marquee_offset(app, width::width(&entry.name) - room), room))
+            } else {
+                width::truncate(&entry.name, room)
+            };
+
+            let mut spans = vec![
+                Span::raw(icon),
+                Span::raw(" "),
+                Span::styled(name, style)
+            ];
+            spans.extend(details);
+            let content = Line::from(spans);
+            ListItem::new(content)
+        })
+        .collect();
+
+    let mut modifiers = String::new();
+    for modifier in app.view_modifiers() {
+        modifiers.push_str(&modifier);
+        modifiers.push(' ');
+    }
+    // Marks belong to the pane, unlike the view settings.
+    if !pane.marked.is_empty() {
+        modifiers.push_str(&i18n::trf("marked_modifier", &[("count", &pane.marked.len())]));
+        modifiers.push(' ');
+    }
+    let template = app.config.contents_title.clone().unwrap_or_else(|| i18n::tr("contents_of"));
+    let tab = app.tab_name_of(pane).cloned().unwrap_or_default();
+    let selected = pane.selected_item
+        .and_then(|i| pane.current_dir_contents.get(i))
+        .filter(|entry| !entry.parent)
+        .map_or("", |entry| entry.name.as_str());
+    let count = pane.current_dir_contents.iter().filter(|e| !e.parent).count();
+    let title_with = |path: &str| {
+        i18n::fill(&template, &[("path", &path), ("tab", &tab), ("count", &count), ("selected", &selected)])
+    };
+    // Keep the modifiers visible and shorten the path from the left.
+    let path = app.display_path_of(pane);
+    let path_room = (area.width as usize)
+        .saturating_sub(2 + width::width(&title_with("")) + 2 + width::width(&modifiers));
+    let contents = title_with(&width::truncate_start(&path, path_room));
+    let title = format!(" {} {}", contents, modifiers);
+    let list_block = if focused { focused_block(app) } else { block(&app.config) }.title(title);
+    // Rows are highlighted above, so the state is only here for scrolling.
+    let height = list_block.inner(area).height as usize;
+    let list = List::new(items).block(list_block);
+
+    let offset = scroll_offset(
+        pane.scroll_offset.get(),
+        pane.selected_item,
+        pane.current_dir_contents.len(),
+        height,
+        app.config.scroll_margin,
+    );
+    pane.scroll_offset.set(offset);
+    let mut state = ListState::default().with_selected(pane.selected_item).with_offset(offset);
+    f.render_stateful_widget(list, area, &mut state);
+}
+
+/// The first row to show so `selected` stays at least `margin` rows from
+/// the top and bottom of a `height`-row list, moving as little as possible
+/// from `previous`.
+fn scroll_offset(previous: usize, selected: Option<usize>, len: usize, height: usize, margin: usize) -> usize {
+    let Some(selected) = selected else {
+        return 0;
+    };
+    // Past half the height the margins would overlap; keep the selection
+    // centred instead.
+    let margin = margin.min(height.saturating_sub(1) / 2);
+    let mut offset = previous;
+    if selected < offset + margin {
+        offset = selected.saturating_sub(margin);
+    }
+    if selected + margin >= offset + height {
+        offset = (selected + margin + 1).saturating_sub(height);
+    }
+    offset.min(len.saturating_sub(height))
+}
+
+/// Draws the preview pane: the selected file's outline when outline mode is
+/// on and the language is recognised, otherwise its first lines.
+fn render_preview(f: &mut ratatui::Frame, app: &App, area: ratatui::layout::Rect) {
+    let preview_block = if app.preview_focused { focused_block(app) } else { block(&app.config) };
+    // Titled, the block loses its top row even without borders.
+    let inner = preview_block.clone().title(" ").inner(area);
+    let Some(preview) = &app.preview else {
+        f.render_widget(preview_block.title(format!(" {} ", i18n::tr("preview"))), area);
+        return;
+    };
+    if let (true, Some(symbols)) = (app.outline_mode, &preview.outline) {
+        let items: Vec<ListItem> = if symbols.is_empty() {
+            vec![ListItem::new(Span::styled(i18n::tr("no_definitions"), app.theme.muted))]
+        } else {
+            symbols
+                .iter()
+                .enumerate()
+                .map(|(i, symbol)| {
+                    let style = if i == app.outline_selected { app.theme.selected } else { app.theme.text };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{:>5} ", symbol.line), app.theme.muted),
+                        Span::styled(format!("{} ", symbol.kind), app.theme.accent),
+                        Span::styled(symbol.name.as_str(), style),
+                    ]))
+                })
+                .collect()
+        };
+        let list = List::new(items)
+            .block(preview_block.title(format!(" {} ", i18n::tr("outline"))));
+        f.render_widget(list, area);
+        return;
+    }
+    preview.view_height.set(inner.height);
+    let lines: Vec<Line> = match &preview.body {
+        PreviewBody::Text(lines) => lines
+            .iter()
+            .skip(app.preview_scroll)
+            .take(area.height as usize)
+            .map(|line| Line::from(Span::styled(line.as_str(), app.theme.text)))
+            .collect(),
+        PreviewBody::Diff(lines) => lines
+            .iter()
+            .skip(app.preview_scroll)
+            .take(area.height as usize)
+            .map(|line| {
+                let style = match line.as_bytes().first() {
+                    _ if line.starts_with("+++") || line.starts_with("---") => app.theme.muted,
+                    Some(b'+') => app.theme.success,
+                    Some(b'-') => app.theme.error,
+                    Some(b'@') => app.theme.accent,
+                    Some(b' ') => app.theme.text,
+                    _ => app.theme.muted,
+                };
+                Line::from(Span::styled(line.as_str(), style))
+            })
+            .collect(),
+        PreviewBody::Dir(children) if app.shown_children(children).next().is_none() => vec![Line::from(Span::styled(i18n::tr("empty_dir"), app.theme.muted))],
+        PreviewBody::Dir(children) => app
+            .shown_children(children)
+            .skip(app.preview_scroll)
+            .take(area.height as usize)
+            .map(|child| {
+                let (icon, style) = if child.is_dir { ("📁", app.theme.directory) } else { ("📄", app.theme.file) };
+                Line::from(vec![Span::raw(icon), Span::raw(" "), Span::styled(child.name.as_str(), style)])
+            })
+            .collect(),
+        PreviewBody::Binary => vec![Line::from(Span::styled(i18n::tr("binary_file"), app.theme.muted))],
+        PreviewBody::Image { width, height } => {
+            // The image goes under the size line, inside the borders.
+            let room = Rect {
+                y: inner.y + 1,
+                height: inner.height.saturating_sub(1),
+                ..inner
+            };
+            if room.width > 0 && room.height > 0 {
+                app.image_area.set(Some(room));
+            }
+            let kind = preview.path.extension().map_or(String::new(), |e| e.to_string_lossy().to_uppercase());
+            vec![Line::from(Span::styled(i18n::trf("image_info", &[("kind", &kind), ("width", width), ("height", height)]), app.theme.muted))]
+        },
+        PreviewBody::Error(e) => vec![Line::from(Span::styled(i18n::trf("cant_preview", &[("error", e)]), app.theme.error))],
+    };
+    let title = match (app.outline_mode, preview.loading) {
+        (_, true) => "preview_loading",
+        _ if matches!(preview.body, PreviewBody::Diff(_)) => "preview_diff",
+        (true, false) => "preview_no_outline",
+        (false, false) if app.diff_mode => "preview_no_diff",
+        (false, false) => "preview",
+    };
+    let title = format!(" {} ", i18n::tr(title));
+    f.render_widget(Paragraph::new(lines).block(preview_block.title(title)), area);
+}
+
+/// Widths for the date, name and time cells in proportion to `split`. When
+/// that leaves any cell too narrow for its text, the name cell is dropped
+/// and the other two share the width.
+fn header_constraints(width: u16, cells: [&str; 3], split: [u32; 3]) -> Vec<Constraint> {
+    let fits = |split: [u32; 3]| {
+        let total = split.iter().sum::<u32>().max(1);
+        cells.iter()
+            .zip(split)
+            .all(|(text, share)| share == 0 || width as u32 * share / total >= width::width(text) as u32 + 2)
+    };
+    let split = if fits(split) { split } else { [split[0].max(1), 0, split[2].max(1)] };
+    let total = split.iter().sum::<u32>().max(1);
+    split.iter().map(|&share| Constraint::Ratio(share, total)).collect()
+}
+
+/// A tab bar title, `key_label` or `key_icon label`, with the label cut to
+/// `max` columns when set. The key and icon are never cut, so the tab can
+/// still be picked out by them; the full name shows in the status bar path
+/// once the tab is open.
+fn tab_title(key: &str, icon: Option<&str>, label: &str, max: Option<usize>) -> String {
+    let label = match max {
+        Some(max) => width::truncate(label, max),
+        None => label.into(),
+    };
+    match icon {
+        Some(icon) => format!("{}_{} {}", key, icon, label),
+        None => format!("{}_{}", key, label),
+    }
+}
+
+/// Compact description of the tab keys for the shortcuts bar, e.g. `1-9`.
+fn tab_keys_hint(keys: &str) -> String {
+    let first = keys.chars().next();
+    let last = keys.chars().last();
+    match (first, last) {
+        (Some(first), Some(last)) if keys.chars().count() > 2 => format!("{}-{}", first, last),
+        _ => keys.to_string(),
+    }
+}
+
+/// Bordered block styled according to the user's config. Every block in the
+/// UI goes through here so border settings apply consistently.
+fn block(config: &Config) -> Block<'static> {
+    if config.border_style.is_none() {
+        // A column of padding keeps text off the panel next door.
+        return Block::default().padding(Padding::horizontal(1));
+    }
+    Block::default()
+        .borders(Borders::ALL)
+        .border_type(config.border_style.into())
+}
+
+/// `block` for the panel keys go to. The accent goes on the title as well
+/// when there's no border to carry it.
+fn focused_block(app: &App) -> Block<'static> {
+    let focused = block(&app.config).border_style(app.theme.accent);
+    if app.config.border_style.is_none() {
+        focused.title_style(app.theme.accent)
+    } else {
+        focused
+    }
+}
+
+/// A popup just big enough for `width` × `height` cells of content plus
+/// its border, centred in `r` and cut down to fit inside it.
+fn fitted_rect(width: u16, height: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
+    let width = width.saturating_add(2).min(r.width);
+    let height = height.saturating_add(2).min(r.height);
+    ratatui::layout::Rect {
+        x: r.x + (r.width - width) / 2,
+        y: r.y + (r.height - height) / 2,
+        width,
+        height,
+    }
+}
+
 fn centered_rect(percent_x: u16, percent_y: u16, r: ratatui::layout::Rect) -> ratatui::layout::Rect {
     let popup_layout = Layout::default()
         .direction(Direction::Vertical)
@@ -0,0 +1,73 @@
+use std::{fs, path::Path};
+
+/// Name and version a project declares in its manifest, which is often
+/// friendlier than the directory name.
+#[derive(Clone)]
+pub struct ProjectInfo {
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl ProjectInfo {
+    /// Reads the first manifest found in `dir`: `Cargo.toml`, then
+    /// `package.json`, then `pyproject.toml`. Missing or unparseable
+    /// manifests are not an error; they just give `None`.
+    pub fn detect(dir: &Path) -> Option<ProjectInfo> {
+        cargo(dir).or_else(|| npm(dir)).or_else(|| python(dir))
+    }
+
+    /// `name v1.2.3`, or just the name when there's no version.
+    pub fn label(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} v{}", self.name, version),
+            None => self.name.clone(),
+        }
+    }
+}
+
+/// A sensible run command for the project in `dir`, guessed from which
+/// build files it has.
+pub fn default_task(dir: &Path) -> Option<&'static str> {
+    if dir.join("Cargo.toml").is_file() {
+        Some("cargo run")
+    } else if dir.join("package.json").is_file() {
+        Some("npm start")
+    } else if dir.join("go.mod").is_file() {
+        Some("go run .")
+    } else if dir.join("Makefile").is_file() {
+        Some("make")
+    } else {
+        None
+    }
+}
+
+fn cargo(dir: &Path) -> Option<ProjectInfo> {
+    let manifest: toml::Table = fs::read_to_string(dir.join("Cargo.toml")).ok()?.parse().ok()?;
+    from_toml(manifest.get("package")?)
+}
+
+fn npm(dir: &Path) -> Option<ProjectInfo> {
+    let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(dir.join("package.json")).ok()?).ok()?;
+    Some(ProjectInfo {
+        name: manifest.get("name")?.as_str()?.to_string(),
+        version: manifest.get("version").and_then(|v| v.as_str()).map(str::to_owned),
+    })
+}
+
+/// PEP 621 `[project]`, falling back to Poetry's `[tool.poetry]`.
+fn python(dir: &Path) -> Option<ProjectInfo> {
+    let manifest: toml::Table = fs::read_to_string(dir.join("pyproject.toml")).ok()?.parse().ok()?;
+    manifest.get("project").and_then(from_toml).or_else(|| {
+        from_toml(manifest.get("tool")?.get("poetry")?)
+    })
+}
+
+/// Reads `name` and `version` from a manifest table. A version inherited
+/// from a workspace (`version.workspace = true`) isn't a string and is
+/// treated as absent.
+fn from_toml(table: &toml::Value) -> Option<ProjectInfo> {
+    Some(ProjectInfo {
+        name: table.get("name")?.as_str()?.to_string(),
+        version: table.get("version").and_then(|v| v.as_str()).map(str::to_owned),
+    })
+}
@@ -0,0 +1,35 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::PathExt;
+
+/// Where the note for `project` lives: one Markdown file per project under
+/// `~/.config/bod/notes`.
+pub fn path(project: &str) -> io::Result<PathBuf> {
+    Ok(Path::new("~/.config/bod/notes").expand_home()?.join(format!("{}.md", project)))
+}
+
+/// Reads the note for `project`; a project without one has an empty note.
+pub fn read(project: &str) -> io::Result<String> {
+    match fs::read_to_string(path(project)?) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the note file for `project`, creating it empty if needed so the
+/// editor opens a real file.
+pub fn ensure_exists(project: &str) -> io::Result<PathBuf> {
+    let path = path(project)?;
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, "")?;
+    }
+    Ok(path)
+}
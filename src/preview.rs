@@ -0,0 +1,376 @@
+use std::{
+    cell::Cell,
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+use crate::{archive::{self, ArchiveFormat}, graphics};
+
+/// Files are only read this far. Reading stops well short of it unless the
+/// preview is scrolled that far down or the outline needs the whole file.
+const MAX_PREVIEW_BYTES: u64 = 4 * 1024 * 1024;
+
+/// How much the reader thread reads between checks for more lines wanted.
+const CHUNK_BYTES: usize = 16 * 1024;
+
+/// The contents of the selected file as shown in the preview pane.
+///
+/// The file is read on a background thread a chunk at a time, and only as
+/// far as `want_lines` has asked for, so a large file neither blocks the UI
+/// nor gets read in full just to show its first screen. `poll` picks up
+/// whatever lines have arrived.
+pub struct Preview {
+    pub path: PathBuf,
+    /// Loaded with `diff` set, so showing git's changes where there are any.
+    pub diff: bool,
+    pub body: PreviewBody,
+    /// Top-level symbols, or `None` when the language isn't recognised or
+    /// the file is still loading.
+    pub outline: Option<Vec<Symbol>>,
+    /// Set until the whole file, up to the size cap, has been read.
+    pub loading: bool,
+    /// Rows the preview showed last frame, set while rendering so callers
+    /// know how far ahead to ask for lines.
+    pub view_height: Cell<u16>,
+    want: Sender<usize>,
+    rx: Receiver<Message>,
+}
+
+pub enum PreviewBody {
+    Text(Vec<String>),
+    /// `git diff` output for a file with uncommitted changes.
+    Diff(Vec<String>),
+    /// What's directly inside a directory, subdirectories first.
+    Dir(Vec<DirChild>),
+    Binary,
+    /// A picture, with its size in pixels. Drawn by `graphics` where the
+    /// terminal can; otherwise only the size is shown.
+    Image { width: u32, height: u32 },
+    Error(String),
+}
+
+enum Message {
+    Lines(Vec<String>),
+    Diff(Vec<String>),
+    Dir(Vec<DirChild>),
+    Binary,
+    Image { width: u32, height: u32 },
+    Error(String),
+    Done,
+}
+
+/// An entry in a previewed directory.
+pub struct DirChild {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+/// A top-level definition found by `outline`.
+pub struct Symbol {
+    /// 1-based, as editors count them.
+    pub line: usize,
+    pub kind: &'static str,
+    pub name: String,
+}
+
+impl Preview {
+    /// Starts reading `path`. Nothing is read until `want_lines` is called.
+    /// With `diff`, a file git reports changes for shows those instead. A
+    /// directory is listed rather than read.
+    pub fn load(path: &Path, diff: bool) -> Preview {
+        let thread_path = path.to_path_buf();
+        Preview::spawn(path, diff, move |want, tx| {
+            // Only an image's header is read, for its size.
+            if thread_path.is_dir() {
+                list_dir(&thread_path).map(|children| {
+                    let _ = tx.send(Message::Dir(children));
+                })
+            } else if graphics::is_image(&thread_path) {
+                image::image_dimensions(&thread_path)
+                    .map(|(width, height)| {
+                        let _ = tx.send(Message::Image { width, height });
+                    })
+                    .map_err(io::Error::other)
+            } else if diff && send_diff(&thread_path, tx) {
+                Ok(())
+            } else {
+                read_lines(File::open(&thread_path)?, want, tx)
+            }
+        })
+    }
+
+    /// Starts reading the file `member` of `archive` the way `load` reads
+    /// a file. Its start is unpacked into memory, up to the usual size cap,
+    /// and nothing is written to disk. The path is the archive's joined
+    /// with the member's, which names nothing on disk.
+    pub fn load_member(archive: &Path, format: ArchiveFormat, member: &Path) -> Preview {
+        let (archive, member) = (archive.to_path_buf(), member.to_path_buf());
+        Preview::spawn(&archive.join(&member), false, move |want, tx| {
+            let bytes = archive::read_member(&archive, format, &member, MAX_PREVIEW_BYTES)?;
+            read_lines(bytes.as_slice(), want, tx)
+        })
+    }
+
+    /// A directory listing that's already known, like one inside an
+    /// archive, shown under `path`.
+    pub fn listing(path: &Path, mut children: Vec<DirChild>) -> Preview {
+        sort_children(&mut children);
+        Preview::spawn(path, false, move |_, tx| {
+            let _ = tx.send(Message::Dir(children));
+            Ok(())
+        })
+    }
+
+    /// Runs `read` on a background thread to feed the preview of `path`.
+    fn spawn(
+        path: &Path,
+        diff: bool,
+        read: impl FnOnce(&Receiver<usize>, &Sender<Message>) -> io::Result<()> + Send + 'static,
+    ) -> Preview {
+        let (want_tx, want_rx) = mpsc::channel();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let result = read(&want_rx, &tx);
+            let message = match result {
+                Ok(()) => Message::Done,
+                Err(e) => Message::Error(e.to_string()),
+            };
+            let _ = tx.send(message);
+        });
+        Preview {
+            path: path.to_path_buf(),
+            diff,
+            body: PreviewBody::Text(Vec::new()),
+            outline: None,
+            loading: true,
+            view_height: Cell::new(0),
+            want: want_tx,
+            rx,
+        }
+    }
+
+    /// Asks for the file to be read at least as far as line `lines`.
+    pub fn want_lines(&self, lines: usize) {
+        if self.loading {
+            let _ = self.want.send(lines);
+        }
+    }
+
+    /// Collects whatever has been read so far. Never blocks.
+    pub fn poll(&mut self) {
+        while self.loading {
+            match self.rx.try_recv() {
+                Ok(Message::Lines(lines)) => {
+                    if let PreviewBody::Text(body) = &mut self.body {
+                        body.extend(lines);
+                    }
+                },
+                Ok(Message::Diff(lines)) => self.body = PreviewBody::Diff(lines),
+                Ok(Message::Dir(children)) => self.body = PreviewBody::Dir(children),
+                Ok(Message::Binary) => self.body = PreviewBody::Binary,
+                Ok(Message::Image { width, height }) => self.body = PreviewBody::Image { width, height },
+                Ok(Message::Error(e)) => {
+                    self.body = PreviewBody::Error(e);
+                    self.loading = false;
+                },
+                Ok(Message::Done) => {
+                    self.loading = false;
+                    if let (PreviewBody::Text(lines), Some(language)) = (&self.body, Language::detect(&self.path)) {
+                        self.outline = Some(outline(lines, &language));
+                    }
+                },
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.body = PreviewBody::Error("preview thread exited unexpectedly".to_string());
+                    self.loading = false;
+                },
+            }
+        }
+    }
+}
+
+/// The entries directly inside `dir`, subdirectories first and each group
+/// by name.
+fn list_dir(dir: &Path) -> io::Result<Vec<DirChild>> {
+    let mut children: Vec<DirChild> = fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| DirChild {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir: entry.path().is_dir(),
+        })
+        .collect();
+    sort_children(&mut children);
+    Ok(children)
+}
+
+/// Subdirectories first, then each group by name.
+fn sort_children(children: &mut [DirChild]) {
+    children.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+}
+
+/// Sends git's diff of `path` against the last commit. False when there's
+/// nothing to show: the file is unchanged or untracked, isn't in a
+/// repository, or git isn't installed.
+fn send_diff(path: &Path, tx: &Sender<Message>) -> bool {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return false;
+    };
+    let output = Command::new("git")
+        .args(["diff", "--no-color", "HEAD", "--"])
+        .arg(name)
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output();
+    match output {
+        Ok(output) if output.status.success() && !output.stdout.is_empty() => {
+            let lines = String::from_utf8_lossy(&output.stdout).lines().map(str::to_owned).collect();
+            let _ = tx.send(Message::Diff(lines));
+            true
+        },
+        _ => false,
+    }
+}
+
+/// Reads `source` a chunk at a time, sending complete lines as they turn
+/// up and pausing whenever more lines have been sent than were asked for.
+/// Returns early, without error, once the `Preview` is dropped.
+fn read_lines(source: impl Read, want: &Receiver<usize>, tx: &Sender<Message>) -> io::Result<()> {
+    let mut file = source.take(MAX_PREVIEW_BYTES);
+    let mut wanted = 0;
+    let mut sent = 0;
+    let mut first = true;
+    let mut partial = Vec::new();
+    let mut chunk = vec![0; CHUNK_BYTES];
+    loop {
+        // The newest request is the one that counts.
+        while let Ok(lines) = want.try_recv() {
+            wanted = wanted.max(lines);
+        }
+        if sent >= wanted {
+            match want.recv() {
+                Ok(lines) => wanted = wanted.max(lines),
+                Err(_) => return Ok(()),
+            }
+            continue;
+        }
+
+        let read = file.read(&mut chunk)?;
+        if first && chunk[..read].contains(&0) {
+            let _ = tx.send(Message::Binary);
+            return Ok(());
+        }
+        first = false;
+        if read == 0 {
+            if !partial.is_empty() {
+                let _ = tx.send(Message::Lines(vec![decode(&partial)]));
+            }
+            return Ok(());
+        }
+
+        partial.extend_from_slice(&chunk[..read]);
+        let Some(last_newline) = partial.iter().rposition(|&b| b == b'\n') else {
+            continue;
+        };
+        let lines: Vec<String> = partial[..last_newline].split(|&b| b == b'\n').map(decode).collect();
+        partial.drain(..=last_newline);
+        sent += lines.len();
+        if tx.send(Message::Lines(lines)).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+/// One line of the file as text, without its line ending. Invalid UTF-8 is
+/// shown lossily.
+fn decode(line: &[u8]) -> String {
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
+}
+
+/// Just enough of a language's syntax to spot top-level definitions: the
+/// keywords that start one and the modifiers that may come before them.
+struct Language {
+    modifiers: &'static [&'static str],
+    keywords: &'static [&'static str],
+}
+
+impl Language {
+    fn detect(path: &Path) -> Option<Language> {
+        let language = match path.extension()?.to_str()? {
+            "rs" => Language {
+                modifiers: &["pub(crate)", "pub(super)", "pub", "async", "const", "unsafe", "extern \"C\"", "default"],
+                keywords: &["fn", "struct", "enum", "trait", "impl", "mod", "type", "union", "macro_rules!"],
+            },
+            "py" => Language {
+                modifiers: &["async"],
+                keywords: &["def", "class"],
+            },
+            "js" | "jsx" | "mjs" | "ts" | "tsx" => Language {
+                modifiers: &["export", "default", "declare", "abstract", "async"],
+                keywords: &["function", "class", "interface", "type", "enum"],
+            },
+            "go" => Language {
+                modifiers: &[],
+                keywords: &["func", "type"],
+            },
+            "rb" => Language {
+                modifiers: &[],
+                keywords: &["def", "class", "module"],
+            },
+            _ => return None,
+        };
+        Some(language)
+    }
+}
+
+/// Finds definitions that start at column zero. Not a parser: anything
+/// indented is assumed to be nested and skipped.
+fn outline(lines: &[String], language: &Language) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        if line.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let mut rest = line.as_str();
+        while let Some(after) = language.modifiers.iter().find_map(|m| strip_word(rest, m)) {
+            rest = after;
+        }
+        for &keyword in language.keywords {
+            let Some(after) = strip_word(rest, keyword) else {
+                continue;
+            };
+            // Go methods put the receiver before the name.
+            let after = match after.strip_prefix('(') {
+                Some(receiver) => receiver.split_once(')').map_or("", |(_, name)| name.trim_start()),
+                None => after,
+            };
+            let name = if keyword == "impl" {
+                after.split(['{', ';']).next().unwrap_or("").trim().trim_end_matches(" where")
+            } else {
+                let end = after.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$')).unwrap_or(after.len());
+                &after[..end]
+            };
+            if !name.is_empty() {
+                symbols.push(Symbol { line: index + 1, kind: keyword, name: name.to_string() });
+            }
+            break;
+        }
+    }
+    symbols
+}
+
+/// Strips `word` and the whitespace after it, requiring that whitespace so
+/// `fn` doesn't match `fnord`. `macro_rules!`-style words ending in
+/// punctuation don't need it.
+fn strip_word<'a>(line: &'a str, word: &str) -> Option<&'a str> {
+    let after = line.strip_prefix(word)?;
+    let needs_space = word.ends_with(|c: char| c.is_alphanumeric());
+    if needs_space && !after.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(after.trim_start())
+}
@@ -0,0 +1,47 @@
+use std::{
+    cmp::Reverse,
+    path::PathBuf,
+    time::SystemTime,
+};
+use walkdir::WalkDir;
+
+/// Most files the recent files view lists.
+const LIMIT: usize = 100;
+
+/// A file in one of the tabs and when it last changed.
+pub struct RecentFile {
+    pub tab: String,
+    /// Relative to the tab's root.
+    pub path: PathBuf,
+    pub modified: SystemTime,
+}
+
+/// The most recently modified files under each `(tab, root)`, newest
+/// first. Looks at most `depth` directories down and skips dot
+/// directories, where version control and tool state live. Unreadable
+/// entries are left out.
+pub fn scan(roots: &[(String, PathBuf)], depth: usize) -> Vec<RecentFile> {
+    let mut files = Vec::new();
+    for (tab, root) in roots {
+        let walker = WalkDir::new(root)
+            .min_depth(1)
+            .max_depth(depth)
+            .into_iter()
+            .filter_entry(|e| !(e.file_type().is_dir() && e.file_name().to_string_lossy().starts_with('.')));
+        for entry in walker.flatten() {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) else {
+                continue;
+            };
+            let Ok(path) = entry.path().strip_prefix(root) else {
+                continue;
+            };
+            files.push(RecentFile { tab: tab.clone(), path: path.to_path_buf(), modified });
+        }
+    }
+    files.sort_by_key(|f| Reverse(f.modified));
+    files.truncate(LIMIT);
+    files
+}
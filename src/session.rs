@@ -0,0 +1,68 @@
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{PathDisplay, PathExt, SortMode};
+
+/// How often a running app checks whether the session changed and saves it.
+pub const AUTOSAVE_SECS: u64 = 5;
+
+/// View settings toggled in the app and remembered between runs.
+///
+/// Unlike `Config`, which the user writes and bod only ever edits one key
+/// at a time, this file belongs to bod: it's rewritten in full on every
+/// exit. Anything missing or unreadable falls back to the defaults.
+#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct Session {
+    pub show_hidden: bool,
+    pub show_preview: bool,
+    pub outline_mode: bool,
+    pub diff_mode: bool,
+    pub sort_mode: SortMode,
+    pub sort_reverse: bool,
+    pub path_display: PathDisplay,
+    pub dual_pane: bool,
+}
+
+impl Default for Session {
+    fn default() -> Session {
+        Session {
+            show_hidden: true,
+            show_preview: false,
+            outline_mode: false,
+            diff_mode: false,
+            sort_mode: SortMode::Name,
+            sort_reverse: false,
+            path_display: PathDisplay::Base,
+            dual_pane: false,
+        }
+    }
+}
+
+impl Session {
+    pub fn path() -> io::Result<PathBuf> {
+        Path::new("~/.config/bod/session.toml").expand_home()
+    }
+
+    /// The last saved session, or the defaults if there isn't a usable one.
+    pub fn load() -> Session {
+        Session::path()
+            .and_then(fs::read_to_string)
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Session::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+}
@@ -0,0 +1,174 @@
+use std::{io, path::Path};
+use crossterm::event::{self, Event, KeyCode};
+use ratatui::{
+    text::{Line, Span},
+    widgets::{Clear, Paragraph, Wrap},
+};
+use toml_edit::value;
+
+use crate::{
+    block, centered_rect,
+    complete::{self, Completion},
+    config::{self, Config},
+    i18n,
+    launch::Tui,
+    theme::{self, Theme, ThemeName},
+    PathExt,
+};
+
+/// Themes offered, by their name in the config.
+const THEMES: &[(ThemeName, &str)] = &[
+    (ThemeName::Default, "default"),
+    (ThemeName::Gruvbox, "gruvbox"),
+    (ThemeName::Nord, "nord"),
+    (ThemeName::Terminal, "terminal"),
+    (ThemeName::Monochrome, "monochrome"),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Step {
+    ProjectsDir,
+    Editor,
+    Theme,
+}
+
+struct Setup {
+    step: Step,
+    projects_dir: String,
+    editor: String,
+    theme: usize,
+    /// Checked once, as it may run `tput`.
+    colors: bool,
+    completion: Option<Completion>,
+    error: Option<String>,
+}
+
+/// Asks for the projects directory, editor and theme on a first run, then
+/// writes them into a fresh config file and returns the result. Esc skips
+/// the questions and writes only the commented defaults, so either way the
+/// next run goes straight to the UI.
+pub fn run(terminal: &mut Tui) -> io::Result<Config> {
+    // The app proper loads this too, once it knows the configured locale.
+    let _ = i18n::load(None);
+    let mut setup = Setup {
+        step: Step::ProjectsDir,
+        projects_dir: config::DEFAULT_PROJECTS_DIR.to_string(),
+        editor: "nvim".to_string(),
+        theme: 0,
+        colors: theme::colors_supported(),
+        completion: None,
+        error: None,
+    };
+    loop {
+        draw(terminal, &setup)?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.code != KeyCode::Tab {
+            setup.completion = None;
+        }
+        let text = match setup.step {
+            Step::ProjectsDir => Some(&mut setup.projects_dir),
+            Step::Editor => Some(&mut setup.editor),
+            Step::Theme => None,
+        };
+        match (key.code, text) {
+            (KeyCode::Esc, _) => {
+                Config::ensure_exists()?;
+                return Config::load();
+            },
+            (KeyCode::Enter, _) => {
+                setup.error = None;
+                match setup.step {
+                    Step::ProjectsDir => match Path::new(setup.projects_dir.trim()).expand_home() {
+                        Ok(path) if path.is_dir() => setup.step = Step::Editor,
+                        result => {
+                            let problem = if result.is_ok_and(|path| path.exists()) { "projects_not_dir" } else { "no_projects_dir" };
+                            setup.error = Some(i18n::trf(problem, &[("path", &setup.projects_dir.trim())]));
+                        },
+                    },
+                    Step::Editor if setup.editor.trim().is_empty() => {},
+                    Step::Editor => setup.step = Step::Theme,
+                    Step::Theme => return finish(&setup),
+                }
+            },
+            (KeyCode::Up, None) => setup.theme = setup.theme.saturating_sub(1),
+            (KeyCode::Down, None) => setup.theme = (setup.theme + 1).min(THEMES.len() - 1),
+            (KeyCode::Tab, Some(text)) => match setup.completion.as_mut() {
+                Some(completion) => completion.cycle(text),
+                None => {
+                    let candidates = match setup.step {
+                        Step::ProjectsDir => complete::path_completions(text, Path::new("."), true),
+                        _ => complete::command_completions(text, Path::new(".")),
+                    };
+                    setup.completion = Completion::start(text, candidates);
+                },
+            },
+            (KeyCode::Backspace, Some(text)) => {
+                text.pop();
+            },
+            (KeyCode::Char(c), Some(text)) => text.push(c),
+            _ => {},
+        }
+    }
+}
+
+/// Writes the answers over the commented defaults, keeping the comments.
+fn finish(setup: &Setup) -> io::Result<Config> {
+    Config::ensure_exists()?;
+    config::set_key("projects_dir", value(setup.projects_dir.trim()))?;
+    config::set_key("editor", value(setup.editor.trim()))?;
+    config::set_key("theme", value(THEMES[setup.theme].1))?;
+    Config::load()
+}
+
+fn draw(terminal: &mut Tui, setup: &Setup) -> io::Result<()> {
+    // The theme step shows each theme as it's picked.
+    let name = if setup.step == Step::Theme { THEMES[setup.theme].0 } else { ThemeName::Default };
+    let theme = if setup.colors { Theme::named(name) } else { Theme::monochrome() };
+    let (number, question) = match setup.step {
+        Step::ProjectsDir => (1, "setup_projects_dir"),
+        Step::Editor => (2, "setup_editor"),
+        Step::Theme => (3, "setup_theme"),
+    };
+    let mut lines = vec![
+        Line::from(Span::styled(i18n::tr("setup_intro"), theme.muted)),
+        Line::from(""),
+        Line::from(Span::styled(format!("{}/3 {}", number, i18n::tr(question)), theme.accent)),
+    ];
+    match setup.step {
+        Step::ProjectsDir | Step::Editor => {
+            let text = if setup.step == Step::Editor { &setup.editor } else { &setup.projects_dir };
+            lines.push(Line::from(vec![
+                Span::styled(text.as_str(), theme.text),
+                Span::styled("█", theme.text),
+            ]));
+            if let Some(completion) = &setup.completion {
+                lines.push(Line::from(Span::styled(completion.candidates.join("  "), theme.muted)));
+            }
+        },
+        Step::Theme => {
+            for (i, (_, name)) in THEMES.iter().enumerate() {
+                let style = if i == setup.theme { theme.selected } else { theme.text };
+                lines.push(Line::from(Span::styled(*name, style)));
+            }
+        },
+    }
+    if let Some(error) = &setup.error {
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(error.as_str(), theme.error)));
+    }
+    lines.push(Line::from(""));
+    let keys = if setup.step == Step::Theme { "setup_keys_list" } else { "setup_keys_input" };
+    lines.push(Line::from(Span::styled(i18n::tr(keys), theme.muted)));
+
+    terminal.draw(|f| {
+        let area = centered_rect(60, 50, f.size());
+        let panel = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(block(&Config::default()).title(format!(" {} ", i18n::tr("setup_title"))));
+        f.render_widget(Clear, area);
+        f.render_widget(panel, area);
+    })?;
+    Ok(())
+}
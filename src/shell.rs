@@ -0,0 +1,150 @@
+use std::{
+    cell::Cell,
+    env,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    process::{Command, ExitStatus, Stdio},
+    sync::mpsc::{self, Receiver, Sender, TryRecvError},
+    thread,
+};
+
+/// A shell command run from the `:` prompt or as a project task, plus
+/// whatever it has printed so far.
+///
+/// The command runs in the background and its output is streamed in line
+/// by line, so a slow `cargo build` shows progress without freezing the
+/// UI; `poll` picks up whatever has arrived.
+pub struct CommandOutput {
+    pub command: String,
+    pub lines: Vec<OutputLine>,
+    pub status: CommandStatus,
+    pub scroll: u16,
+    /// Keep the newest output in view. Scrolling up turns it off and
+    /// scrolling back to the bottom turns it on again.
+    pub follow: bool,
+    /// Rows the output view showed last frame, set while rendering so
+    /// scrolling can stop at the last line.
+    pub view_height: Cell<u16>,
+    rx: Receiver<Message>,
+}
+
+/// `text` single-quoted for `sh`, so it's passed as one word whatever it
+/// contains.
+pub fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+pub struct OutputLine {
+    pub text: String,
+    pub stderr: bool,
+}
+
+pub enum CommandStatus {
+    Running,
+    /// Exit code, or `None` when the process was killed by a signal.
+    Exited(Option<i32>),
+    Failed(String),
+}
+
+enum Message {
+    Line(OutputLine),
+    Exited(io::Result<ExitStatus>),
+}
+
+impl CommandOutput {
+    /// Runs `command` through `$SHELL -c` with `dir` as the working directory.
+    pub fn spawn(command: &str, dir: &Path) -> CommandOutput {
+        let shell = env::var("SHELL").unwrap_or_else(|_| "sh".to_string());
+        let (tx, rx) = mpsc::channel();
+        let mut cmd = Command::new(shell);
+        cmd.arg("-c")
+            .arg(command)
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        log::info!("running {:?} in {}", command, dir.display());
+        match cmd.spawn() {
+            Ok(mut child) => {
+                let readers = [
+                    child.stdout.take().map(|out| stream_lines(out, false, tx.clone())),
+                    child.stderr.take().map(|err| stream_lines(err, true, tx.clone())),
+                ];
+                thread::spawn(move || {
+                    // Every line is sent before the exit status.
+                    for reader in readers.into_iter().flatten() {
+                        let _ = reader.join();
+                    }
+                    let _ = tx.send(Message::Exited(child.wait()));
+                });
+            },
+            Err(e) => {
+                log::error!("couldn't run {:?}: {}", command, e);
+                let _ = tx.send(Message::Exited(Err(e)));
+            },
+        }
+
+        CommandOutput {
+            command: command.to_string(),
+            lines: Vec::new(),
+            status: CommandStatus::Running,
+            scroll: 0,
+            follow: true,
+            view_height: Cell::new(0),
+            rx,
+        }
+    }
+
+    /// Collects whatever output has arrived. Never blocks.
+    pub fn poll(&mut self) {
+        loop {
+            match self.rx.try_recv() {
+                Ok(Message::Line(line)) => self.lines.push(line),
+                Ok(Message::Exited(Ok(status))) => self.status = CommandStatus::Exited(status.code()),
+                Ok(Message::Exited(Err(e))) => self.status = CommandStatus::Failed(e.to_string()),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if matches!(self.status, CommandStatus::Running) {
+                        self.status = CommandStatus::Failed("command thread exited unexpectedly".to_string());
+                    }
+                    break;
+                },
+            }
+        }
+        if self.follow {
+            self.scroll = self.max_scroll();
+        }
+    }
+
+    fn max_scroll(&self) -> u16 {
+        let hidden = self.lines.len().saturating_sub(self.view_height.get() as usize);
+        hidden.min(u16::MAX as usize) as u16
+    }
+
+    pub fn scroll_up(&mut self, amount: u16) {
+        self.scroll = self.scroll.saturating_sub(amount);
+        self.follow = false;
+    }
+
+    pub fn scroll_down(&mut self, amount: u16) {
+        self.scroll = self.scroll.saturating_add(amount).min(self.max_scroll());
+        self.follow = self.scroll == self.max_scroll();
+    }
+}
+
+/// Forwards each line of `stream` as it arrives. Invalid UTF-8 is shown
+/// lossily rather than cutting the output short.
+fn stream_lines(stream: impl Read + Send + 'static, stderr: bool, tx: Sender<Message>) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut buf = Vec::new();
+        while matches!(reader.read_until(b'\n', &mut buf), Ok(n) if n > 0) {
+            let text = String::from_utf8_lossy(&buf).trim_end_matches(['\n', '\r']).to_string();
+            if tx.send(Message::Line(OutputLine { text, stderr })).is_err() {
+                break;
+            }
+            buf.clear();
+        }
+    })
+}
@@ -0,0 +1,218 @@
+use std::{env, io, process::Command};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+
+use crate::config::ColorRule;
+
+/// Every style the UI draws with. Rendering code never picks colours
+/// itself; it asks the active theme.
+#[derive(Clone)]
+pub struct Theme {
+    /// Regular text.
+    pub text: Style,
+    /// Key hints, prompts and popup borders.
+    pub accent: Style,
+    pub directory: Style,
+    pub file: Style,
+    /// Symlinks pointing at nothing.
+    pub broken_link: Style,
+    /// The row under the cursor.
+    pub selected: Style,
+    /// Rows marked for C and M.
+    pub marked: Style,
+    /// The row under the cursor when it's also marked.
+    pub selected_marked: Style,
+    /// Secondary details such as child counts.
+    pub muted: Style,
+    pub success: Style,
+    pub error: Style,
+    /// Inverted label at the start of the status bar.
+    pub badge: Style,
+    /// Titles in the tab bar.
+    pub tab: Style,
+    /// The current tab's title.
+    pub active_tab: Style,
+    /// The current tab's title with `tab_highlight = "background"`.
+    pub active_tab_filled: Style,
+}
+
+/// Theme names accepted by the `theme` config key.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    #[default]
+    Default,
+    Gruvbox,
+    Nord,
+    /// Inherit the terminal's own foreground and background.
+    Terminal,
+    /// No colour at all; only bold, dim, underline and reverse video.
+    Monochrome,
+}
+
+impl Theme {
+    /// The theme to draw with: `name`, unless the terminal can't show
+    /// colour or the user asked for none, in which case monochrome.
+    pub fn for_terminal(name: ThemeName) -> Theme {
+        if colors_supported() {
+            Theme::named(name)
+        } else {
+            Theme::monochrome()
+        }
+    }
+
+    pub fn named(name: ThemeName) -> Theme {
+        match name {
+            ThemeName::Default => Theme::default_theme(),
+            ThemeName::Gruvbox => Theme::palette(
+                Color::Rgb(235, 219, 178),
+                Color::Rgb(250, 189, 47),
+                Color::Rgb(131, 165, 152),
+                Color::Rgb(146, 131, 116),
+                Color::Rgb(184, 187, 38),
+                Color::Rgb(251, 73, 52),
+            ),
+            ThemeName::Nord => Theme::palette(
+                Color::Rgb(216, 222, 233),
+                Color::Rgb(235, 203, 139),
+                Color::Rgb(136, 192, 208),
+                Color::Rgb(97, 110, 136),
+                Color::Rgb(163, 190, 140),
+                Color::Rgb(191, 97, 106),
+            ),
+            ThemeName::Terminal => Theme::terminal(),
+            ThemeName::Monochrome => Theme::monochrome(),
+        }
+    }
+
+    /// The original hardcoded colours.
+    fn default_theme() -> Theme {
+        Theme::palette(Color::White, Color::Yellow, Color::Cyan, Color::DarkGray, Color::Green, Color::Red)
+    }
+
+    fn palette(text: Color, accent: Color, directory: Color, muted: Color, success: Color, error: Color) -> Theme {
+        Theme {
+            text: Style::default().fg(text),
+            accent: Style::default().fg(accent),
+            directory: Style::default().fg(directory),
+            file: Style::default().fg(text),
+            broken_link: Style::default().fg(error).add_modifier(Modifier::CROSSED_OUT),
+            selected: Style::default().fg(accent).add_modifier(Modifier::BOLD),
+            marked: Style::default().fg(success).add_modifier(Modifier::BOLD),
+            selected_marked: Style::default().fg(Color::Black).bg(success).add_modifier(Modifier::BOLD),
+            muted: Style::default().fg(muted),
+            success: Style::default().fg(success),
+            error: Style::default().fg(error),
+            badge: Style::default().fg(Color::Black).bg(accent),
+            tab: Style::default().fg(text),
+            active_tab: Style::default().fg(accent),
+            active_tab_filled: Style::default().fg(Color::Black).bg(accent).add_modifier(Modifier::BOLD),
+        }
+    }
+
+    /// Uses the terminal's default colours everywhere except the selection,
+    /// relying on modifiers to keep things distinguishable.
+    fn terminal() -> Theme {
+        let plain = Style::default().fg(Color::Reset).bg(Color::Reset);
+        Theme {
+            text: plain,
+            accent: plain.add_modifier(Modifier::BOLD),
+            directory: plain.add_modifier(Modifier::BOLD),
+            file: plain,
+            broken_link: plain.add_modifier(Modifier::CROSSED_OUT),
+            selected: Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            marked: plain.add_modifier(Modifier::UNDERLINED),
+            selected_marked: Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            muted: plain.add_modifier(Modifier::DIM),
+            success: plain,
+            error: plain.add_modifier(Modifier::BOLD),
+            badge: plain.add_modifier(Modifier::REVERSED),
+            tab: plain,
+            active_tab: plain.add_modifier(Modifier::BOLD),
+            active_tab_filled: plain.add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        }
+    }
+
+    /// Tells things apart with modifiers alone, for terminals that can't
+    /// show colour.
+    pub fn monochrome() -> Theme {
+        let plain = Style::default();
+        Theme {
+            text: plain,
+            accent: plain.add_modifier(Modifier::BOLD),
+            directory: plain.add_modifier(Modifier::BOLD),
+            file: plain,
+            broken_link: plain.add_modifier(Modifier::CROSSED_OUT),
+            selected: plain.add_modifier(Modifier::REVERSED),
+            marked: plain.add_modifier(Modifier::UNDERLINED),
+            selected_marked: plain.add_modifier(Modifier::REVERSED | Modifier::UNDERLINED),
+            muted: plain.add_modifier(Modifier::DIM),
+            success: plain,
+            error: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            badge: plain.add_modifier(Modifier::REVERSED),
+            tab: plain,
+            active_tab: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            active_tab_filled: plain.add_modifier(Modifier::BOLD | Modifier::REVERSED),
+        }
+    }
+}
+
+/// False when colour is off: `NO_COLOR` is set to anything non-empty (see
+/// no-color.org), `TERM` is `dumb` or missing, or terminfo reports fewer
+/// than eight colours. If `tput` isn't available colour is assumed.
+pub fn colors_supported() -> bool {
+    if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return false;
+    }
+    match env::var("TERM") {
+        Ok(term) if term != "dumb" => {},
+        _ => return false,
+    }
+    let colors = Command::new("tput")
+        .arg("colors")
+        .output()
+        .ok()
+        .and_then(|out| String::from_utf8(out.stdout).ok())
+        .and_then(|out| out.trim().parse::<i32>().ok());
+    colors.is_none_or(|n| n >= 8)
+}
+
+/// The `color_rules` from the config, ready to match entry names against.
+#[derive(Default)]
+pub struct ColorRules {
+    globs: GlobSet,
+    styles: Vec<Style>,
+}
+
+impl ColorRules {
+    /// Compiles `rules`, failing on the first bad pattern, colour or
+    /// modifier.
+    pub fn new(rules: &[ColorRule]) -> io::Result<ColorRules> {
+        let invalid = |what: String| io::Error::new(io::ErrorKind::InvalidData, format!("color_rules: {}", what));
+        let mut globs = GlobSetBuilder::new();
+        let mut styles = Vec::new();
+        for rule in rules {
+            globs.add(Glob::new(&rule.pattern).map_err(|e| invalid(e.to_string()))?);
+            let mut style = Style::default();
+            if let Some(color) = &rule.color {
+                let color = color.parse::<Color>().map_err(|_| invalid(format!("unknown colour {:?}", color)))?;
+                style = style.fg(color);
+            }
+            if let Some(modifier) = &rule.modifier {
+                let flag = Modifier::from_name(&modifier.to_ascii_uppercase())
+                    .ok_or_else(|| invalid(format!("unknown modifier {:?}", modifier)))?;
+                style = style.add_modifier(flag);
+            }
+            styles.push(style);
+        }
+        let globs = globs.build().map_err(|e| invalid(e.to_string()))?;
+        Ok(ColorRules { globs, styles })
+    }
+
+    /// The style of the first rule whose pattern matches `name`.
+    pub fn style_for(&self, name: &str) -> Option<Style> {
+        let first = self.globs.matches(name).into_iter().min()?;
+        self.styles.get(first).copied()
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+/// How often the status bar's free space is checked again.
+pub const FREE_SPACE_SECS: u64 = 30;
+
+/// Recursive directory sizes, calculated on background threads and kept
+/// per path so asking again is instant.
+pub struct DiskUsage {
+    results: HashMap<PathBuf, Usage>,
+    tx: Sender<(PathBuf, io::Result<u64>)>,
+    rx: Receiver<(PathBuf, io::Result<u64>)>,
+}
+
+pub enum Usage {
+    Calculating,
+    Done(u64),
+    Failed(String),
+}
+
+impl DiskUsage {
+    pub fn new() -> DiskUsage {
+        let (tx, rx) = mpsc::channel();
+        DiskUsage { results: HashMap::new(), tx, rx }
+    }
+
+    /// Starts calculating the size of `dir` unless it's already known or
+    /// being worked on. A previous failure is retried.
+    pub fn request(&mut self, dir: &Path) {
+        if matches!(self.results.get(dir), Some(Usage::Calculating | Usage::Done(_))) {
+            return;
+        }
+        self.results.insert(dir.to_path_buf(), Usage::Calculating);
+        let tx = self.tx.clone();
+        let dir = dir.to_path_buf();
+        thread::spawn(move || {
+            let size = dir_size(&dir);
+            let _ = tx.send((dir, size));
+        });
+    }
+
+    pub fn get(&self, dir: &Path) -> Option<&Usage> {
+        self.results.get(dir)
+    }
+
+    /// Collects any finished calculations. Never blocks.
+    pub fn poll(&mut self) {
+        while let Ok((dir, size)) = self.rx.try_recv() {
+            let usage = match size {
+                Ok(size) => Usage::Done(size),
+                Err(e) => Usage::Failed(e.to_string()),
+            };
+            self.results.insert(dir, usage);
+        }
+    }
+}
+
+/// Sums the sizes of every file under `dir`. Symlinks are counted as links
+/// and never followed, so a link cycle can't hang the walk. Entries that
+/// can't be read are skipped; only an unreadable `dir` itself is an error.
+fn dir_size(dir: &Path) -> io::Result<u64> {
+    let mut total = 0;
+    for entry in fs::read_dir(dir)?.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            total += dir_size(&entry.path()).unwrap_or(0);
+        } else {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
+/// Bytes free for ordinary users on the filesystem holding `path`.
+#[cfg(unix)]
+pub fn free_space(path: &Path) -> io::Result<u64> {
+    use std::{ffi::CString, mem::MaybeUninit, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)?;
+    let mut stats = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated, and `stats` is only read after
+    // statvfs reports it filled in.
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stats.assume_init()
+    };
+    // The field types differ between platforms.
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn free_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "free space is only known on Unix"))
+}
+
+/// Formats a byte count with binary units, e.g. `4.2 MiB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
@@ -0,0 +1,85 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::PathExt;
+
+/// How often and how lately each tab was switched to, kept between runs
+/// for `tab_order = "frecency"`.
+///
+/// Like `Session`, the file belongs to bod and is rewritten in full.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Visits {
+    tabs: HashMap<String, Visit>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct Visit {
+    count: u32,
+    /// Seconds since the Unix epoch.
+    last: u64,
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+impl Visit {
+    /// Visits weighted by how long ago the last one was, as in `z` and
+    /// `zoxide`: a tab used a lot but not for weeks ranks below one used
+    /// a little today.
+    fn frecency(self, now: u64) -> f64 {
+        let age = now.saturating_sub(self.last);
+        let weight = match age {
+            a if a < 60 * 60 => 4.0,
+            a if a < 24 * 60 * 60 => 2.0,
+            a if a < 7 * 24 * 60 * 60 => 1.0,
+            _ => 0.25,
+        };
+        self.count as f64 * weight
+    }
+}
+
+impl Visits {
+    pub fn path() -> io::Result<PathBuf> {
+        Path::new("~/.config/bod/visits.toml").expand_home()
+    }
+
+    /// The saved visits, or none if there isn't a usable file.
+    pub fn load() -> Visits {
+        Visits::path()
+            .and_then(fs::read_to_string)
+            .ok()
+            .and_then(|text| toml::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = Visits::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+        fs::write(path, text)
+    }
+
+    pub fn record(&mut self, tab: &str) {
+        let visit = self.tabs.entry(tab.to_string()).or_insert(Visit { count: 0, last: 0 });
+        visit.count += 1;
+        visit.last = now();
+    }
+
+    /// Every visited tab, highest frecency first.
+    pub fn ranked(&self) -> Vec<String> {
+        let now = now();
+        let mut tabs: Vec<(&String, f64)> = self.tabs.iter().map(|(tab, visit)| (tab, visit.frecency(now))).collect();
+        tabs.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        tabs.into_iter().map(|(tab, _)| tab.clone()).collect()
+    }
+}
@@ -0,0 +1,70 @@
+use std::borrow::Cow;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Number of terminal columns `s` occupies. CJK characters and most emoji
+/// take two, combining marks take none.
+pub fn width(s: &str) -> usize {
+    UnicodeWidthStr::width(s)
+}
+
+/// Cuts `s` down to at most `max` columns, ending in `…` when anything was
+/// dropped. A double-width character is never split in half.
+pub fn truncate(s: &str, max: usize) -> Cow<'_, str> {
+    if width(s) <= max {
+        return Cow::Borrowed(s);
+    }
+    if max == 0 {
+        return Cow::Borrowed("");
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > max - 1 {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    out.push('…');
+    Cow::Owned(out)
+}
+
+/// The part of `s` from `offset` columns in, at most `max` columns wide.
+/// A double-width character cut by either edge is left out.
+pub fn window(s: &str, offset: usize, max: usize) -> String {
+    let mut out = String::new();
+    let mut column = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if column >= offset && column + w <= offset + max {
+            out.push(c);
+        }
+        column += w;
+    }
+    out
+}
+
+/// Like `truncate`, but keeps the end of `s`, which is the interesting part
+/// of a path.
+pub fn truncate_start(s: &str, max: usize) -> Cow<'_, str> {
+    if width(s) <= max {
+        return Cow::Borrowed(s);
+    }
+    if max == 0 {
+        return Cow::Borrowed("");
+    }
+    let mut kept = Vec::new();
+    let mut used = 0;
+    for c in s.chars().rev() {
+        let w = c.width().unwrap_or(0);
+        if used + w > max - 1 {
+            break;
+        }
+        kept.push(c);
+        used += w;
+    }
+    let mut out = String::from('…');
+    out.extend(kept.into_iter().rev());
+    Cow::Owned(out)
+}